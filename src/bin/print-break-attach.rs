@@ -0,0 +1,74 @@
+//! Attach to a program's breakpoints from another terminal.
+//!
+//! Run the program with `PRINT_BREAK_SOCKET=/tmp/pb.sock`, then:
+//!
+//! ```text
+//! print-break-attach /tmp/pb.sock
+//! ```
+//!
+//! Breakpoint frames are shown here and the usual commands (Enter, m, t, s, q, ...)
+//! are sent back to the paused program.
+
+#[cfg(unix)]
+fn main() {
+    use std::io::{self, BufRead, Read, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let Some(path) = std::env::args()
+        .nth(1)
+        .or_else(|| std::env::var("PRINT_BREAK_SOCKET").ok())
+        .filter(|p| !p.is_empty())
+    else {
+        eprintln!("usage: print-break-attach <socket path>  (or set PRINT_BREAK_SOCKET)");
+        std::process::exit(2);
+    };
+
+    // The program creates the socket at its first breakpoint, so keep trying
+    let mut announced = false;
+    let mut stream = loop {
+        match UnixStream::connect(&path) {
+            Ok(stream) => break stream,
+            Err(e) => {
+                if !announced {
+                    eprintln!("\x1b[90mwaiting for {} ({})...\x1b[0m", path, e);
+                    announced = true;
+                }
+                std::thread::sleep(Duration::from_millis(500));
+            }
+        }
+    };
+    eprintln!("\x1b[90mattached to {}, waiting for a breakpoint...\x1b[0m", path);
+
+    // Frames and prompts from the program, passed through as-is
+    let mut reader = stream.try_clone().expect("failed to clone socket");
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+        let mut stdout = io::stdout();
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(n) => {
+                    let _ = stdout.write_all(&buf[..n]);
+                    let _ = stdout.flush();
+                }
+            }
+        }
+        eprintln!("\n\x1b[90m(program exited)\x1b[0m");
+        std::process::exit(0);
+    });
+
+    // Commands typed here, one per line
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if stream.write_all(format!("{}\n", line).as_bytes()).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("print-break-attach needs Unix domain sockets, which this platform doesn't support");
+    std::process::exit(1);
+}
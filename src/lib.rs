@@ -7,11 +7,19 @@
 //!
 //! - Pretty-prints any `Debug` type with syntax highlighting
 //! - Auto-detects and formats JSON, TOML, YAML strings with colors
-//! - Shows file:line location and elapsed time between breakpoints
+//! - Custom rendering per type with [`BreakFormat`] or [`register_formatter`]
+//! - Shows file:line location, thread and elapsed time between breakpoints
+//! - Thread-safe: breakpoints from different threads queue up instead of interleaving
+//! - Per-location hit counts with `print_break_nth!`, `print_break_every!`, `print_break_after!`
+//! - Watch breakpoints with `print_break_changed!` that only pause when a value changes
 //! - Pauses execution until you press Enter
-//! - **Compiles to nothing in release builds**
+//! - **Compiles to nothing in release builds** (opt back in with the `release` feature)
 //! - **Disable at runtime with `PRINT_BREAK=0`**
 //! - **Non-TTY safe** - won't hang in CI/piped output
+//! - Output goes to stderr, or to a file, buffer or your own [`Sink`] via [`set_sink`]
+//! - Test what your breakpoints show with [`capture`]
+//! - Uses `/dev/tty` for the prompt, so programs with redirected stdin or stderr can be paused too
+//! - Works inside raw-mode TUI apps: the terminal is restored for the prompt and put back after
 //!
 //! ## Usage
 //!
@@ -25,28 +33,58 @@
 //! print_break!(x, name, json);
 //! ```
 //!
+//! ## Cargo Features
+//!
+//! - `json`, `toml`, `yaml` (default) - Detect and pretty-print strings in these formats
+//! - `backtrace` (default) - Stack traces with the `t` command
+//! - `serde` - `print_break_serde!` renders `Serialize` values as JSON
+//! - `release` - Keep breakpoints active in release builds
+//! - `disabled` - Compile breakpoints to nothing even in debug builds (wins over `release`)
+//!
 //! ## Environment Variables
 //!
 //! - `PRINT_BREAK=0` - Disable all breakpoints
 //! - `PRINT_BREAK=1` - Enable breakpoints (default)
+//! - `PRINT_BREAK=trace` - Print frames without pausing (like [`print_trace!`])
 //! - `PRINT_BREAK_DEPTH=N` - Max nesting depth before collapsing (default: 4)
 //! - `PRINT_BREAK_STYLE=X` - Border style: `rounded`, `sharp`, `double`, `ascii`
+//! - `PRINT_BREAK_LOG=path.jsonl` - Append a JSON Lines record for every breakpoint hit
+//! - `PRINT_BREAK_TIMEOUT=N` - Auto-continue after N seconds at the prompt (Unix)
+//! - `PRINT_BREAK_CONTEXT=N` - Show N lines of source above and below the breakpoint in each frame
+//! - `PRINT_BREAK_DIFF=1` - Show what changed since the previous hit in each frame
+//! - `PRINT_BREAK_SOCKET=path` - Serve breakpoints on a Unix socket for `print-break-attach`
+//! - `PRINT_BREAK_SCRIPT=path` - Answer prompts with the commands in a file, in order
+//! - `PRINT_BREAK_SCRIPT_DEFAULT=cmd` - Command once the script runs out (default: `continue`, `prompt` to ask)
 //!
 //! ## Interactive Controls
 //!
 //! When paused at a breakpoint:
 //! - **Enter** - Continue to next breakpoint
 //! - **m** - Show full output (if truncated)
+//! - **p** - Open the full output in `$PAGER` (default `less -R`)
 //! - **t** - Show stack trace
+//! - **d** - Diff each value against the previous hit of this breakpoint
+//! - **l [N]** - List the source around the breakpoint (N lines each side, default 5)
+//! - **e** - Open the breakpoint line in `$VISUAL` / `$EDITOR`
 //! - **c** - Copy value to clipboard
+//! - **w PATH [VAR]** - Write the values (or one variable) to a file, as JSON for `.json` paths
+//! - **x PATH [N]** - Expand a collapsed value, e.g. `x user.roles[2]` (N = depth)
+//! - **/TEXT** - Search every value and show matches with their paths
+//! - **i** - Ignore this breakpoint location (others keep breaking)
+//! - **b** - List ignored breakpoint locations
+//! - **u N** - Re-enable ignored location N (`u all` re-enables every one)
 //! - **s** - Skip all remaining breakpoints
 //! - **q** - Quit the program immediately
 //! - **h / ?** - Show help
 
+use std::any::{Any, TypeId};
+use std::collections::{BTreeMap, VecDeque};
 use std::fmt::Debug;
 use std::io::IsTerminal;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
-use std::sync::Mutex;
+use std::sync::{Mutex, MutexGuard, PoisonError, RwLock};
 use std::time::Instant;
 
 /// Global flag to skip all remaining breakpoints
@@ -58,6 +96,38 @@ static BREAK_COUNT: AtomicUsize = AtomicUsize::new(0);
 /// Last breakpoint timestamp for elapsed time
 static LAST_BREAK_TIME: Mutex<Option<Instant>> = Mutex::new(None);
 
+/// Serializes breakpoints across threads so frames and prompts don't interleave
+static BREAK_LOCK: Mutex<()> = Mutex::new(());
+
+/// Number of threads queued behind the breakpoint currently being shown
+static WAITING: AtomicUsize = AtomicUsize::new(0);
+
+/// Per-site breakpoint state, keyed by `file!()` and `line!()`
+static SITES: Mutex<BTreeMap<(&'static str, u32), SiteState>> = Mutex::new(BTreeMap::new());
+
+/// State tracked for a single breakpoint location
+#[derive(Default)]
+struct SiteState {
+    /// Set by the `i` command - this location no longer breaks
+    ignored: bool,
+    /// Number of times this location has been reached
+    hits: usize,
+    /// Values from the previous hit, for `print_break_changed!`
+    last_values: Option<Vec<String>>,
+    /// Hits since the last change that were counted without pausing
+    unchanged: usize,
+    /// `CARGO_MANIFEST_DIR` of the crate the breakpoint is in
+    manifest_dir: Option<&'static str>,
+    /// Values shown by the last frame at this location, for the `d` command
+    last_frame: Option<LastFrame>,
+}
+
+/// A location's previously shown values by name
+struct LastFrame {
+    hit: usize,
+    values: Vec<(&'static str, String)>,
+}
+
 /// Border style characters
 #[derive(Clone, Copy)]
 pub struct BorderStyle {
@@ -164,10 +234,12 @@ impl Colors {
         reset: "",
     };
 
-    /// Get colors based on TTY detection
+    /// Get colors based on TTY detection (remote clients are assumed to be
+    /// terminals). An installed [`Sink`] decides for itself.
     #[inline]
     pub fn get() -> Self {
-        if is_tty() { Self::TTY } else { Self::PLAIN }
+        let terminal = if sink_installed() { sink_is_terminal() } else { is_tty() };
+        if terminal || remote_attached() { Self::TTY } else { Self::PLAIN }
     }
 }
 
@@ -207,6 +279,7 @@ pub fn update_break_time() {
 const MAX_LINES: usize = 50;
 
 /// Colorize JSON output
+#[cfg(feature = "json")]
 fn colorize_json(s: &str) -> String {
     let c = Colors::get();
     if c.cyan.is_empty() {
@@ -262,6 +335,12 @@ fn colorize_json(s: &str) -> String {
                 result.push_str(reset);
                 context_stack.pop();
             }
+            '…' if !in_string => {
+                // Collapsed object/array from `print_break_serde!`
+                result.push_str(gray);
+                result.push(c);
+                result.push_str(reset);
+            }
             '0'..='9' | '-' | '.' if !in_string => {
                 result.push_str(yellow);
                 result.push(c);
@@ -319,6 +398,7 @@ fn colorize_json(s: &str) -> String {
 }
 
 /// Colorize TOML output
+#[cfg(feature = "toml")]
 fn colorize_toml(s: &str) -> String {
     let c = Colors::get();
     if c.cyan.is_empty() {
@@ -361,6 +441,7 @@ fn colorize_toml(s: &str) -> String {
     result.trim_end().to_string()
 }
 
+#[cfg(feature = "toml")]
 fn colorize_toml_value(s: &str, magenta: &str, yellow: &str, gray: &str, reset: &str) -> String {
     let trimmed = s.trim();
 
@@ -387,6 +468,7 @@ fn colorize_toml_value(s: &str, magenta: &str, yellow: &str, gray: &str, reset:
 }
 
 /// Colorize YAML output
+#[cfg(feature = "yaml")]
 fn colorize_yaml(s: &str) -> String {
     let c = Colors::get();
     if c.cyan.is_empty() {
@@ -454,6 +536,7 @@ fn colorize_yaml(s: &str) -> String {
     result.trim_end().to_string()
 }
 
+#[cfg(feature = "yaml")]
 fn colorize_yaml_value(s: &str, magenta: &str, yellow: &str, reset: &str) -> String {
     let trimmed = s.trim();
 
@@ -481,10 +564,129 @@ pub fn is_enabled() -> bool {
     }
 }
 
+/// Check if breakpoints should print without pausing (`PRINT_BREAK=trace`)
+#[doc(hidden)]
+pub fn is_trace_mode() -> bool {
+    matches!(std::env::var("PRINT_BREAK").as_deref(), Ok("trace"))
+}
+
+/// Check if the breakpoint at `file:line` should fire
+#[doc(hidden)]
+pub fn is_enabled_at(file: &'static str, line: u32) -> bool {
+    if is_capturing() {
+        return true;
+    }
+    if !is_enabled() {
+        return false;
+    }
+    match SITES.lock() {
+        Ok(sites) => !sites.get(&(file, line)).is_some_and(|site| site.ignored),
+        Err(_) => true,
+    }
+}
+
+/// Ignore (or re-enable) the breakpoint at `file:line`
+#[doc(hidden)]
+pub fn set_site_ignored(file: &'static str, line: u32, ignored: bool) {
+    if let Ok(mut sites) = SITES.lock() {
+        sites.entry((file, line)).or_default().ignored = ignored;
+    }
+}
+
+/// List ignored breakpoint locations in a stable (file, line) order
+fn ignored_sites() -> Vec<(&'static str, u32)> {
+    match SITES.lock() {
+        Ok(sites) => sites
+            .iter()
+            .filter(|(_, site)| site.ignored)
+            .map(|(&key, _)| key)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// Record a hit at `file:line` and return the location's hit count (1-based).
+/// `manifest_dir` is the breakpoint crate's root, used to find its source
+/// (`None` when the crate wasn't built by cargo).
+#[doc(hidden)]
+pub fn record_hit(file: &'static str, line: u32, manifest_dir: Option<&'static str>) -> usize {
+    match SITES.lock() {
+        Ok(mut sites) => {
+            let site = sites.entry((file, line)).or_default();
+            site.hits += 1;
+            site.manifest_dir = manifest_dir;
+            site.hits
+        }
+        Err(_) => 1,
+    }
+}
+
+/// Find the source file of the breakpoint at `file:line` on disk.
+///
+/// `file!()` is relative to the workspace root, which may be any ancestor of
+/// the crate's manifest directory, so each of those is tried in turn before
+/// the current directory (the only place looked in without a manifest
+/// directory).
+fn source_path(file: &'static str, line: u32) -> Option<std::path::PathBuf> {
+    let path = std::path::Path::new(file);
+    let manifest_dir = SITES.lock().ok().and_then(|sites| sites.get(&(file, line))?.manifest_dir);
+    manifest_dir
+        .into_iter()
+        .flat_map(|dir| std::path::Path::new(dir).ancestors())
+        .map(|dir| dir.join(path))
+        .chain(std::iter::once(path.to_path_buf()))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Wait for other threads' breakpoints to finish, then hold the breakpoint lock
+fn lock_breakpoint() -> MutexGuard<'static, ()> {
+    WAITING.fetch_add(1, Ordering::Relaxed);
+    let guard = BREAK_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    WAITING.fetch_sub(1, Ordering::Relaxed);
+    guard
+}
+
+/// Prompt prefix for threads queued behind the current breakpoint
+fn waiting_note(waiting: usize, c: &Colors) -> String {
+    match waiting {
+        0 => String::new(),
+        1 => format!("{}(1 thread waiting){} ", c.yellow, c.reset),
+        n => format!("{}({} threads waiting){} ", c.yellow, n, c.reset),
+    }
+}
+
+/// Name of the current thread, or its id if unnamed
+fn thread_label() -> String {
+    let thread = std::thread::current();
+    match thread.name() {
+        Some(name) => name.to_string(),
+        None => format!("{:?}", thread.id()),
+    }
+}
+
 /// Check if we're running in a TTY (interactive terminal)
+///
+/// Frames and commands use the controlling terminal when there is one, so
+/// programs with redirected stdin or stderr (`cmd < input.json 2>err.log`)
+/// can still be paused. An installed [`Sink`] only changes where frames go,
+/// not whether breakpoints pause.
 #[doc(hidden)]
 pub fn is_tty() -> bool {
-    std::io::stderr().is_terminal() && std::io::stdin().is_terminal()
+    has_controlling_tty() || (std::io::stderr().is_terminal() && std::io::stdin().is_terminal())
+}
+
+/// Whether `/dev/tty` can be opened (checked once)
+fn has_controlling_tty() -> bool {
+    static HAS_TTY: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *HAS_TTY.get_or_init(|| open_tty().is_some())
+}
+
+/// Open the controlling terminal for reading commands
+fn open_tty() -> Option<std::fs::File> {
+    #[cfg(unix)]
+    return std::fs::OpenOptions::new().read(true).write(true).open("/dev/tty").ok();
+    #[cfg(not(unix))]
+    return None;
 }
 
 /// Get and increment breakpoint counter
@@ -503,7 +705,7 @@ pub fn set_skip_all(skip: bool) {
 /// Falls back to Debug formatting otherwise.
 /// Truncates output if it exceeds MAX_LINES.
 #[doc(hidden)]
-pub fn format_value<T: Debug>(value: &T) -> String {
+pub fn format_value<T: Debug + ?Sized>(value: &T) -> String {
     let debug_str = format!("{:?}", value);
     let raw_output;
 
@@ -516,12 +718,14 @@ pub fn format_value<T: Debug>(value: &T) -> String {
             .replace("\\t", "\t")
             .replace("\\\\", "\\");
 
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
         let trimmed = unescaped.trim();
 
         let c = Colors::get();
         let (gray, reset) = (c.gray, c.reset);
 
         // Try JSON first (most specific - must start with { or [)
+        #[cfg(feature = "json")]
         if trimmed.starts_with('{') || trimmed.starts_with('[') {
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&unescaped) {
                 if let Ok(pretty) = serde_json::to_string_pretty(&json) {
@@ -533,6 +737,7 @@ pub fn format_value<T: Debug>(value: &T) -> String {
         }
 
         // Try TOML (look for key = value or [section] patterns)
+        #[cfg(feature = "toml")]
         if trimmed.contains(" = ") || trimmed.contains("]\n") || trimmed.starts_with('[') {
             if let Ok(toml_val) = toml::from_str::<toml::Value>(&unescaped) {
                 if let Ok(pretty) = toml::to_string_pretty(&toml_val) {
@@ -544,6 +749,7 @@ pub fn format_value<T: Debug>(value: &T) -> String {
         }
 
         // Try YAML (look for key: value patterns, but not just any colon)
+        #[cfg(feature = "yaml")]
         if trimmed.contains(": ") || trimmed.contains(":\n") {
             if let Ok(yaml_val) = serde_yaml::from_str::<serde_yaml::Value>(&unescaped) {
                 // Only use YAML if it parsed into something structured (not just a string)
@@ -570,7 +776,7 @@ pub fn format_value<T: Debug>(value: &T) -> String {
 
 /// Format value without truncation (for "more" output)
 #[doc(hidden)]
-pub fn format_value_full<T: Debug>(value: &T) -> String {
+pub fn format_value_full<T: Debug + ?Sized>(value: &T) -> String {
     let debug_str = format!("{:?}", value);
 
     // Check if it's a string
@@ -582,9 +788,11 @@ pub fn format_value_full<T: Debug>(value: &T) -> String {
             .replace("\\t", "\t")
             .replace("\\\\", "\\");
 
+        #[cfg(any(feature = "json", feature = "toml", feature = "yaml"))]
         let trimmed = unescaped.trim();
 
         // Try JSON
+        #[cfg(feature = "json")]
         if trimmed.starts_with('{') || trimmed.starts_with('[') {
             if let Ok(json) = serde_json::from_str::<serde_json::Value>(&unescaped) {
                 if let Ok(pretty) = serde_json::to_string_pretty(&json) {
@@ -594,6 +802,7 @@ pub fn format_value_full<T: Debug>(value: &T) -> String {
         }
 
         // Try TOML
+        #[cfg(feature = "toml")]
         if trimmed.contains(" = ") || trimmed.contains("]\n") || trimmed.starts_with('[') {
             if let Ok(toml_val) = toml::from_str::<toml::Value>(&unescaped) {
                 if let Ok(pretty) = toml::to_string_pretty(&toml_val) {
@@ -603,6 +812,7 @@ pub fn format_value_full<T: Debug>(value: &T) -> String {
         }
 
         // Try YAML
+        #[cfg(feature = "yaml")]
         if trimmed.contains(": ") || trimmed.contains(":\n") {
             if let Ok(yaml_val) = serde_yaml::from_str::<serde_yaml::Value>(&unescaped) {
                 if yaml_val.is_mapping() || yaml_val.is_sequence() {
@@ -621,6 +831,49 @@ pub fn format_value_full<T: Debug>(value: &T) -> String {
     colorize_debug(&format!("{:#?}", value))
 }
 
+/// Pretty-print JSON like `serde_json::to_string_pretty`, collapsing objects
+/// and arrays nested `max_depth` levels deep to `{ … }` / `[ … ]`
+#[cfg(feature = "serde")]
+fn json_collapsed(value: &serde_json::Value, depth: usize, max_depth: usize, out: &mut String) {
+    use serde_json::Value;
+
+    let (open, close, len) = match value {
+        Value::Object(map) if !map.is_empty() => ('{', '}', map.len()),
+        Value::Array(items) if !items.is_empty() => ('[', ']', items.len()),
+        other => {
+            out.push_str(&other.to_string());
+            return;
+        }
+    };
+    if depth >= max_depth {
+        out.push_str(&format!("{} … {}", open, close));
+        return;
+    }
+
+    out.push(open);
+    out.push('\n');
+    let indent = "  ".repeat(depth + 1);
+    let entries: Box<dyn Iterator<Item = (Option<&String>, &Value)>> = match value {
+        Value::Object(map) => Box::new(map.iter().map(|(k, v)| (Some(k), v))),
+        Value::Array(items) => Box::new(items.iter().map(|v| (None, v))),
+        _ => unreachable!(),
+    };
+    for (i, (key, item)) in entries.enumerate() {
+        out.push_str(&indent);
+        if let Some(key) = key {
+            out.push_str(&Value::String(key.clone()).to_string());
+            out.push_str(": ");
+        }
+        json_collapsed(item, depth + 1, max_depth, out);
+        if i + 1 < len {
+            out.push(',');
+        }
+        out.push('\n');
+    }
+    out.push_str(&"  ".repeat(depth));
+    out.push(close);
+}
+
 /// Default maximum nesting depth before collapsing
 const DEFAULT_MAX_DEPTH: usize = 4;
 
@@ -632,127 +885,487 @@ fn max_depth() -> usize {
         .unwrap_or(DEFAULT_MAX_DEPTH)
 }
 
-/// Colorize Debug output for structs/enums
+/// Colorize Debug output for structs/enums, collapsing values nested deeper
+/// than `PRINT_BREAK_DEPTH`
 fn colorize_debug(s: &str) -> String {
     let c = Colors::get();
     if c.cyan.is_empty() {
         return s.to_string();
     }
+    render_debug(s, max_depth(), &c)
+}
 
-    let (green, cyan, yellow, magenta, white, gray, reset) =
-        (c.green, c.cyan, c.yellow, c.magenta, c.white, c.gray, c.reset);
+// ============================================================================
+// Debug output parser
+// ============================================================================
 
-    let mut result = String::new();
-    let lines: Vec<&str> = s.lines().collect();
-    let mut current_depth: usize = 0;
-    let mut skip_until_depth: Option<usize> = None;
+/// A value parsed from `{:?}` / `{:#?}` output
+#[derive(Debug, Clone, PartialEq)]
+enum DebugNode {
+    /// `Name { field: value, .. }`
+    Struct { name: String, fields: Vec<(String, DebugNode)>, non_exhaustive: bool },
+    /// `Name(a, b)`, or a plain tuple `(a, b)` when `name` is empty
+    Tuple { name: String, items: Vec<DebugNode> },
+    /// `[a, b]`
+    List(Vec<DebugNode>),
+    /// `{a, b}`
+    Set(Vec<DebugNode>),
+    /// `{k: v}`
+    Map(Vec<(DebugNode, DebugNode)>),
+    /// A string or char literal, with its quotes and escapes
+    Str(String),
+    Number(String),
+    /// Unit structs and variants, `true`/`false`, and anything that isn't
+    /// shaped like derived Debug output (kept as written)
+    Atom(String),
+}
+
+/// Parse Debug output into a tree. Never fails: text that can't be parsed
+/// becomes an [`DebugNode::Atom`].
+fn parse_debug(s: &str) -> DebugNode {
+    let mut parser = DebugParser { src: s, pos: 0 };
+    let node = parser.value();
+    parser.skip_ws();
+    if parser.pos == s.len() {
+        node
+    } else {
+        DebugNode::Atom(s.trim().to_string())
+    }
+}
+
+/// Recursive descent over Debug output
+struct DebugParser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl DebugParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn rest(&self) -> &str {
+        &self.src[self.pos..]
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_ws();
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn skip_ws(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.bump();
+        }
+    }
 
-    for line in lines {
-        let trimmed = line.trim_start();
-        let indent_count = line.len() - trimmed.len();
-        let indent_level = indent_count / 4;
+    /// One value. Anything not followed by a separator (e.g. a custom Debug
+    /// impl printing `12.50 EUR`) is kept as raw text up to the next one.
+    fn value(&mut self) -> DebugNode {
+        self.skip_ws();
+        let start = self.pos;
+        let node = self.structured();
+        self.skip_ws();
+        match (node, self.peek()) {
+            (Some(node), None | Some(',' | ')' | ']' | '}' | ':')) => node,
+            _ => {
+                self.pos = start;
+                self.raw_atom()
+            }
+        }
+    }
 
-        // Track depth changes
-        let opens = trimmed.ends_with('{') || trimmed.ends_with('[') || trimmed.ends_with('(');
-        let closes = trimmed.starts_with('}') || trimmed.starts_with(']') || trimmed.starts_with(')');
+    fn structured(&mut self) -> Option<DebugNode> {
+        match self.peek()? {
+            '"' | '\'' => self.quoted(),
+            '[' => {
+                self.bump();
+                Some(DebugNode::List(self.items(']')?))
+            }
+            '(' => {
+                self.bump();
+                Some(DebugNode::Tuple { name: String::new(), items: self.items(')')? })
+            }
+            '{' => {
+                self.bump();
+                self.map_or_set()
+            }
+            c if c.is_ascii_digit() || (c == '-' && self.rest()[1..].starts_with(|c: char| c.is_ascii_digit())) => {
+                Some(self.number())
+            }
+            c if c.is_alphabetic() || c == '_' => self.named(),
+            _ => None,
+        }
+    }
 
-        if closes {
-            current_depth = current_depth.saturating_sub(1);
+    /// A string or char literal, escapes included
+    fn quoted(&mut self) -> Option<DebugNode> {
+        let start = self.pos;
+        let quote = self.bump()?;
+        loop {
+            match self.bump()? {
+                '\\' => {
+                    self.bump()?;
+                }
+                c if c == quote => break,
+                _ => {}
+            }
         }
+        Some(DebugNode::Str(self.src[start..self.pos].to_string()))
+    }
 
-        // Check if we're skipping due to depth
-        if let Some(skip_depth) = skip_until_depth {
-            if current_depth < skip_depth {
-                skip_until_depth = None;
+    fn number(&mut self) -> DebugNode {
+        let start = self.pos;
+        self.bump();
+        while let Some(c) = self.peek() {
+            let after_exponent = matches!(self.src[..self.pos].chars().last(), Some('e' | 'E'));
+            if c.is_ascii_alphanumeric() || c == '.' || c == '_' || ((c == '-' || c == '+') && after_exponent) {
+                self.bump();
             } else {
-                if opens {
-                    current_depth += 1;
+                break;
+            }
+        }
+        DebugNode::Number(self.src[start..self.pos].to_string())
+    }
+
+    /// A path, optionally followed by struct fields or tuple items
+    fn named(&mut self) -> Option<DebugNode> {
+        let name = self.path();
+        if self.rest().starts_with('(') {
+            self.bump();
+            return Some(DebugNode::Tuple { name, items: self.items(')')? });
+        }
+        if self.rest().trim_start().starts_with('{') {
+            self.eat('{');
+            return self.fields(name);
+        }
+        Some(DebugNode::Atom(name))
+    }
+
+    /// `std::collections::HashMap<K, V>`, `Some`, `r#type`, ...
+    fn path(&mut self) -> String {
+        let start = self.pos;
+        let mut generics = 0;
+        while let Some(c) = self.peek() {
+            match c {
+                '<' => generics += 1,
+                '>' if generics > 0 => generics -= 1,
+                ',' | ' ' if generics > 0 => {}
+                ':' if self.rest().starts_with("::") => {
+                    self.bump();
                 }
+                c if c.is_alphanumeric() || c == '_' || c == '#' => {}
+                _ => break,
+            }
+            self.bump();
+        }
+        self.src[start..self.pos].to_string()
+    }
+
+    /// Comma separated values up to `close`
+    fn items(&mut self, close: char) -> Option<Vec<DebugNode>> {
+        let mut items = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Some(items);
+            }
+            items.push(self.value());
+            if self.eat(close) {
+                return Some(items);
+            }
+            if !self.eat(',') {
+                return None;
+            }
+        }
+    }
+
+    /// `field: value` pairs after `Name {`
+    fn fields(&mut self, name: String) -> Option<DebugNode> {
+        let mut fields = Vec::new();
+        let mut non_exhaustive = false;
+        loop {
+            if self.eat('}') {
+                return Some(DebugNode::Struct { name, fields, non_exhaustive });
+            }
+            self.skip_ws();
+            if self.rest().starts_with("..") {
+                self.pos += 2;
+                non_exhaustive = true;
                 continue;
             }
+            let field = self.path();
+            if field.is_empty() || !self.eat(':') {
+                return None;
+            }
+            fields.push((field, self.value()));
+            if !self.eat(',') && !self.rest().trim_start().starts_with('}') {
+                return None;
+            }
         }
+    }
 
-        // If we're at max depth and opening a new block, collapse it
-        if opens && current_depth >= max_depth() {
-            // Add indentation guides
-            for _ in 0..indent_level {
-                result.push_str(&format!("{}│{} ", gray, reset));
+    /// `{k: v, ...}` or `{a, b}` after the opening brace
+    fn map_or_set(&mut self) -> Option<DebugNode> {
+        if self.eat('}') {
+            return Some(DebugNode::Map(Vec::new()));
+        }
+        let first = self.value();
+        if !self.eat(':') {
+            let mut items = vec![first];
+            if self.eat(',') {
+                items.extend(self.items('}')?);
+            } else if !self.eat('}') {
+                return None;
+            }
+            return Some(DebugNode::Set(items));
+        }
+        let mut entries = vec![(first, self.value())];
+        loop {
+            if self.eat('}') {
+                return Some(DebugNode::Map(entries));
+            }
+            if !self.eat(',') {
+                return None;
+            }
+            if self.eat('}') {
+                return Some(DebugNode::Map(entries));
+            }
+            let key = self.value();
+            if !self.eat(':') {
+                return None;
             }
+            entries.push((key, self.value()));
+        }
+    }
 
-            // Show collapsed version
-            let name = trimmed.trim_end_matches(['{', '[', '(', ' ']);
-            if !name.is_empty() {
-                result.push_str(&format!("{}{}{} {}{{ ... }}{}", green, name, reset, gray, reset));
-            } else {
-                result.push_str(&format!("{}[ ... ]{}", gray, reset));
+    /// Raw text up to the next separator outside brackets and strings
+    fn raw_atom(&mut self) -> DebugNode {
+        let start = self.pos;
+        let mut depth = 0usize;
+        while let Some(c) = self.peek() {
+            match c {
+                '"' => {
+                    if self.quoted().is_none() {
+                        self.pos = self.src.len();
+                    }
+                    continue;
+                }
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth == 0 => break,
+                ')' | ']' | '}' => depth -= 1,
+                ',' if depth == 0 => break,
+                _ => {}
             }
-            result.push('\n');
+            self.bump();
+        }
+        if self.pos == start {
+            // A stray closing bracket - take it so parsing moves on
+            self.bump();
+        }
+        DebugNode::Atom(self.src[start..self.pos].trim().to_string())
+    }
+}
 
-            skip_until_depth = Some(current_depth);
-            current_depth += 1;
-            continue;
+/// Follow `path` (`.field`, `.0`, `[2]`, `["key"]`) from `node`.
+///
+/// Single-item wrappers like `Some(..)` and `Ok(..)` are stepped through when
+/// a field is looked up on them. On failure, returns the segment not found.
+fn debug_path<'a>(node: &'a DebugNode, path: &str) -> Result<&'a DebugNode, String> {
+    let mut node = node;
+    let mut rest = path;
+    while !rest.is_empty() {
+        let (segment, is_index) = if let Some(after) = rest.strip_prefix('[') {
+            let end = index_end(after).ok_or_else(|| rest.to_string())?;
+            let segment = &after[..end];
+            rest = &after[end + 1..];
+            (segment, true)
+        } else if let Some(after) = rest.strip_prefix('.') {
+            let end = after.find(['.', '[']).unwrap_or(after.len());
+            rest = &after[end..];
+            (&after[..end], false)
+        } else {
+            return Err(rest.to_string());
+        };
+        node = debug_child(node, segment.trim()).ok_or_else(|| {
+            if is_index { format!("[{}]", segment) } else { format!(".{}", segment) }
+        })?;
+    }
+    Ok(node)
+}
+
+/// Position of the `]` closing an index, skipping over quoted keys
+fn index_end(s: &str) -> Option<usize> {
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ']' if !in_string => return Some(i),
+            _ => {}
         }
+    }
+    None
+}
+
+/// One step of [`debug_path`]
+fn debug_child<'a>(node: &'a DebugNode, segment: &str) -> Option<&'a DebugNode> {
+    let index = segment.parse::<usize>().ok();
+    let unquoted = segment.strip_prefix('"').and_then(|s| s.strip_suffix('"')).unwrap_or(segment);
+    let key_matches = |key: &DebugNode| match key {
+        DebugNode::Str(s) => s == segment || s.get(1..s.len().saturating_sub(1)) == Some(unquoted),
+        DebugNode::Number(n) | DebugNode::Atom(n) => n == unquoted,
+        _ => false,
+    };
+    match node {
+        DebugNode::Struct { fields, .. } => fields.iter().find(|(name, _)| name == unquoted).map(|(_, v)| v),
+        DebugNode::List(items) | DebugNode::Set(items) => items.get(index?),
+        DebugNode::Map(entries) => entries.iter().find(|(k, _)| key_matches(k)).map(|(_, v)| v),
+        DebugNode::Tuple { items, .. } => match index.and_then(|i| items.get(i)) {
+            Some(item) => Some(item),
+            // Look through `Some(..)`, `Ok(..)` and other single-item wrappers
+            None if items.len() == 1 => debug_child(&items[0], segment),
+            None => None,
+        },
+        _ => None,
+    }
+}
+
+/// Render Debug output as an indented, colored tree. Values nested
+/// `max_depth` levels deep are collapsed to `Name { ... }`.
+fn render_debug(s: &str, max_depth: usize, c: &Colors) -> String {
+    let mut out = Vec::new();
+    render_node(&parse_debug(s), "", 0, max_depth, "", "", c, &mut out);
+    let lines: Vec<String> = out.into_iter().map(|(_, line)| line).collect();
+    lines.join("\n").trim_end().to_string()
+}
 
-        // Add indentation guides
-        for _ in 0..indent_level {
-            result.push_str(&format!("{}│{} ", gray, reset));
+/// Append the lines for `node` at indentation `depth`, each paired with the
+/// path of the value it shows (`.roles[2].name`, relative to the root).
+/// `lead` goes before the value on its first line (a field name or map key),
+/// `trail` after it (a comma).
+#[allow(clippy::too_many_arguments)]
+fn render_node(node: &DebugNode, path: &str, depth: usize, max_depth: usize, lead: &str, trail: &str, c: &Colors, out: &mut Vec<(String, String)>) {
+    let guides = format!("{}│{} ", c.gray, c.reset).repeat(depth);
+    let comma = |trail: &str| if trail.is_empty() { String::new() } else { format!("{}{}{}", c.gray, trail, c.reset) };
+    let mut push = |line: String| out.push((path.to_string(), line));
+
+    let (name, open, close, len) = match node {
+        DebugNode::Struct { name, fields, non_exhaustive } => (name.as_str(), " {", "}", fields.len() + *non_exhaustive as usize),
+        DebugNode::Tuple { name, items } => (name.as_str(), "(", ")", items.len()),
+        DebugNode::List(items) => ("", "[", "]", items.len()),
+        DebugNode::Set(items) => ("", "{", "}", items.len()),
+        DebugNode::Map(entries) => ("", "{", "}", entries.len()),
+        DebugNode::Atom(text) if text.contains('\n') => {
+            // Multi-line text from a custom Debug impl: keep its lines
+            let lines: Vec<&str> = text.trim_end().lines().collect();
+            for (i, line) in lines.iter().enumerate() {
+                let lead = if i == 0 { lead } else { "" };
+                let trail = if i + 1 == lines.len() { comma(trail) } else { String::new() };
+                push(format!("{}{}{}{}{}{}", guides, lead, c.white, line, c.reset, trail));
+            }
+            return;
         }
+        scalar => {
+            push(format!("{}{}{}{}", guides, lead, color_scalar(scalar, c), comma(trail)));
+            return;
+        }
+    };
 
-        // Colorize the content
-        if opens {
-            // Struct/enum name line: "User {" or "Some(" or "["
-            let name = trimmed.trim_end_matches(['{', '[', '(', ' ']);
-            let bracket = trimmed.chars().last().unwrap_or(' ');
-            if !name.is_empty() {
-                result.push_str(&format!("{}{}{} {}{}{}", green, name, reset, gray, bracket, reset));
-            } else {
-                result.push_str(&format!("{}{}{}", gray, bracket, reset));
-            }
-            current_depth += 1;
-        } else if closes || trimmed.ends_with("},") || trimmed.ends_with("],") || trimmed.ends_with("),") {
-            // Closing brace
-            result.push_str(&format!("{}{}{}", gray, trimmed, reset));
-        } else if trimmed.contains(": ") {
-            // Field: value line
-            if let Some(colon_pos) = trimmed.find(": ") {
-                let field = &trimmed[..colon_pos];
-                let value = &trimmed[colon_pos + 2..];
-                let colored_value = colorize_value(value, yellow, magenta, white, gray, reset);
-                result.push_str(&format!("{}{}{}{}: {}", cyan, field, reset, gray, colored_value));
-            } else {
-                result.push_str(trimmed);
+    let name = if name.is_empty() { String::new() } else { format!("{}{}{}", c.green, name, c.reset) };
+    let open = if name.is_empty() { open.trim_start() } else { open };
+    if len == 0 {
+        // `Name`, `()`, `[]`, `{}`
+        let empty = match node {
+            DebugNode::Struct { .. } => String::new(),
+            _ => format!("{}{}{}{}", c.gray, open.trim_start(), close, c.reset),
+        };
+        push(format!("{}{}{}{}{}", guides, lead, name, empty, comma(trail)));
+        return;
+    }
+    if depth >= max_depth {
+        // `Name { ... }`, `Some( ... )`, `[ ... ]`
+        push(format!("{}{}{}{}{} ... {}{}{}", guides, lead, name, c.gray, open, close, c.reset, comma(trail)));
+        return;
+    }
+
+    push(format!("{}{}{}{}{}{}", guides, lead, name, c.gray, open, c.reset));
+    let key = |k: &str| format!("{}{}{}{}:{} ", c.cyan, k, c.reset, c.gray, c.reset);
+    match node {
+        DebugNode::Struct { fields, non_exhaustive, .. } => {
+            for (field, value) in fields {
+                render_node(value, &format!("{}.{}", path, field), depth + 1, max_depth, &key(field), ",", c, out);
+            }
+            if *non_exhaustive {
+                out.push((path.to_string(), format!("{}{}│ ..{}", guides, c.gray, c.reset)));
             }
-        } else {
-            // Array element or other
-            let colored = colorize_value(trimmed, yellow, magenta, white, gray, reset);
-            result.push_str(&colored);
         }
-        result.push('\n');
+        DebugNode::Tuple { items, .. } => {
+            for (i, item) in items.iter().enumerate() {
+                render_node(item, &format!("{}.{}", path, i), depth + 1, max_depth, "", ",", c, out);
+            }
+        }
+        DebugNode::List(items) | DebugNode::Set(items) => {
+            for (i, item) in items.iter().enumerate() {
+                render_node(item, &format!("{}[{}]", path, i), depth + 1, max_depth, "", ",", c, out);
+            }
+        }
+        DebugNode::Map(entries) => {
+            for (k, value) in entries {
+                let index = format!("{}[{}]", path, inline_node(k, &Colors::PLAIN));
+                render_node(value, &index, depth + 1, max_depth, &format!("{}{}:{} ", inline_node(k, c), c.gray, c.reset), ",", c, out);
+            }
+        }
+        _ => {}
     }
+    out.push((path.to_string(), format!("{}{}{}{}{}", guides, c.gray, close, c.reset, comma(trail))));
+}
 
-    result.trim_end().to_string()
+/// Color a leaf value
+fn color_scalar(node: &DebugNode, c: &Colors) -> String {
+    match node {
+        DebugNode::Str(s) => format!("{}{}{}", c.magenta, s, c.reset),
+        DebugNode::Number(n) => format!("{}{}{}", c.yellow, n, c.reset),
+        DebugNode::Atom(a) if a == "true" || a == "false" => format!("{}{}{}", c.yellow, a, c.reset),
+        DebugNode::Atom(a) => format!("{}{}{}", c.white, a, c.reset),
+        other => inline_node(other, c),
+    }
 }
 
-/// Colorize a single value
-fn colorize_value(s: &str, yellow: &str, magenta: &str, white: &str, gray: &str, reset: &str) -> String {
-    let trimmed = s.trim_end_matches(',');
-    let has_comma = s.ends_with(',');
-    let comma = if has_comma { format!("{},{}", gray, reset) } else { String::new() };
-
-    if trimmed.starts_with('"') {
-        // String value
-        format!("{}{}{}{}", magenta, trimmed, reset, comma)
-    } else if trimmed.parse::<f64>().is_ok() || trimmed.starts_with('-') && trimmed[1..].parse::<f64>().is_ok() {
-        // Number
-        format!("{}{}{}{}", yellow, trimmed, reset, comma)
-    } else if trimmed == "true" || trimmed == "false" {
-        // Boolean
-        format!("{}{}{}{}", yellow, trimmed, reset, comma)
-    } else if trimmed == "None" || trimmed.starts_with("Some(") {
-        // Option
-        format!("{}{}{}{}", white, trimmed, reset, comma)
-    } else {
-        format!("{}{}{}{}", white, trimmed, reset, comma)
+/// Render a node on one line, like `{:?}` with colors (used for map keys)
+fn inline_node(node: &DebugNode, c: &Colors) -> String {
+    let join = |items: Vec<String>| items.join(&format!("{}, {}", c.gray, c.reset));
+    let bracket = |s: &str| format!("{}{}{}", c.gray, s, c.reset);
+    match node {
+        DebugNode::Struct { name, fields, non_exhaustive } => {
+            let mut parts: Vec<String> = fields.iter().map(|(f, v)| format!("{}{}{}: {}", c.cyan, f, c.reset, inline_node(v, c))).collect();
+            if *non_exhaustive {
+                parts.push(bracket(".."));
+            }
+            format!("{}{}{} {} {} {}", c.green, name, c.reset, bracket("{"), join(parts), bracket("}"))
+        }
+        DebugNode::Tuple { name, items } => {
+            format!("{}{}{}{}{}{}", c.green, name, c.reset, bracket("("), join(items.iter().map(|i| inline_node(i, c)).collect()), bracket(")"))
+        }
+        DebugNode::List(items) => format!("{}{}{}", bracket("["), join(items.iter().map(|i| inline_node(i, c)).collect()), bracket("]")),
+        DebugNode::Set(items) => format!("{}{}{}", bracket("{"), join(items.iter().map(|i| inline_node(i, c)).collect()), bracket("}")),
+        DebugNode::Map(entries) => {
+            let parts = entries.iter().map(|(k, v)| format!("{}: {}", inline_node(k, c), inline_node(v, c))).collect();
+            format!("{}{}{}", bracket("{"), join(parts), bracket("}"))
+        }
+        scalar => color_scalar(scalar, c),
     }
 }
 
@@ -798,96 +1411,1489 @@ fn truncate_output(s: &str) -> String {
     }
 }
 
-/// Stored full output for "show more" functionality
-static LAST_FULL_OUTPUT: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+// ============================================================================
+// Output sinks
+// ============================================================================
 
-/// Store full output for potential "show more"
-#[doc(hidden)]
-pub fn store_full_output(output: String) {
-    if let Ok(mut guard) = LAST_FULL_OUTPUT.lock() {
-        *guard = Some(output);
+/// Destination for breakpoint frames, prompts and command output.
+///
+/// Output goes to stderr by default; install another sink with [`set_sink`].
+///
+/// ```rust,no_run
+/// use print_break::{set_sink, FileSink};
+///
+/// set_sink(FileSink::append("breaks.log").unwrap());
+/// ```
+pub trait Sink: Send {
+    /// Write text as-is. It may contain newlines and ANSI color codes.
+    fn write_text(&mut self, text: &str);
+
+    /// Whether the output is shown on a terminal. Colors are only used for
+    /// terminal sinks.
+    fn is_terminal(&self) -> bool {
+        false
     }
 }
 
-/// Show help menu
-fn show_help() {
-    eprintln!("\n\x1b[1;33m─── print-break Help ───\x1b[0m");
-    eprintln!("\x1b[36mEnter\x1b[0m     Continue to next breakpoint");
-    eprintln!("\x1b[36mm\x1b[0m         Show full output (if truncated)");
-    eprintln!("\x1b[36mt\x1b[0m         Show stack trace");
-    eprintln!("\x1b[36mc\x1b[0m         Copy last value to clipboard");
-    eprintln!("\x1b[36ms\x1b[0m         Skip all remaining breakpoints");
-    eprintln!("\x1b[36mq\x1b[0m         Quit the program");
-    eprintln!("\x1b[36mh / ?\x1b[0m     Show this help");
-    eprintln!();
-    eprintln!("\x1b[90mEnvironment variables:\x1b[0m");
-    eprintln!("  \x1b[36mPRINT_BREAK=0\x1b[0m          Disable all breakpoints");
-    eprintln!("  \x1b[36mPRINT_BREAK_DEPTH=N\x1b[0m    Max nesting depth (default: 4)");
-    eprintln!("  \x1b[36mPRINT_BREAK_STYLE=X\x1b[0m    Border style: rounded, sharp, double, ascii");
-    eprintln!("\x1b[1;33m─────────────────────────\x1b[0m\n");
+/// The default sink: standard error
+pub struct StderrSink;
+
+impl Sink for StderrSink {
+    fn write_text(&mut self, text: &str) {
+        use std::io::Write;
+
+        let mut stderr = std::io::stderr().lock();
+        let _ = stderr.write_all(text.as_bytes());
+        let _ = stderr.flush();
+    }
+
+    fn is_terminal(&self) -> bool {
+        std::io::stderr().is_terminal()
+    }
 }
 
-/// Show stack trace
-fn show_stack_trace() {
-    eprintln!("\n\x1b[1;33m─── Stack Trace ───\x1b[0m");
+/// Writes to a file, e.g. a log file or another terminal's `/dev/pts/N`
+pub struct FileSink {
+    file: std::fs::File,
+}
 
-    let bt = backtrace::Backtrace::new();
-    let mut in_relevant = false;
-    let mut count = 0;
+impl FileSink {
+    /// Create (or truncate) the file at `path`
+    pub fn create(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        Ok(Self { file: std::fs::File::create(path)? })
+    }
 
-    for frame in bt.frames() {
-        for symbol in frame.symbols() {
-            if let Some(name) = symbol.name() {
-                let name_str = name.to_string();
+    /// Append to the file at `path`, creating it if needed
+    pub fn append(path: impl AsRef<std::path::Path>) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self { file })
+    }
+}
 
-                // Skip internal frames
-                if name_str.contains("print_break::") || name_str.contains("backtrace::") {
-                    continue;
-                }
+impl From<std::fs::File> for FileSink {
+    fn from(file: std::fs::File) -> Self {
+        Self { file }
+    }
+}
 
-                // Start showing after we exit print_break internals
-                if !in_relevant && !name_str.contains("print_break") {
-                    in_relevant = true;
-                }
+impl Sink for FileSink {
+    fn write_text(&mut self, text: &str) {
+        use std::io::Write;
 
-                if in_relevant {
-                    let file = symbol.filename()
-                        .map(|p| p.display().to_string())
-                        .unwrap_or_default();
-                    let line = symbol.lineno().unwrap_or(0);
+        let _ = self.file.write_all(text.as_bytes());
+    }
 
-                    // Simplify long paths
-                    let short_file = file.rsplit('/').next().unwrap_or(&file);
+    fn is_terminal(&self) -> bool {
+        self.file.is_terminal()
+    }
+}
 
-                    if !name_str.contains("std::") && !name_str.contains("core::") && !name_str.contains("__rust") {
-                        eprintln!("\x1b[90m{:>3}.\x1b[0m \x1b[36m{}\x1b[0m", count, name_str);
-                        if !file.is_empty() && line > 0 {
-                            eprintln!("      \x1b[90mat {}:{}\x1b[0m", short_file, line);
-                        }
-                        count += 1;
+/// Collects output in memory. Clones share the same buffer, so keep one to
+/// read what was written after installing the other.
+///
+/// ```rust
+/// use print_break::{set_sink, reset_sink, MemorySink};
+///
+/// let sink = MemorySink::new();
+/// set_sink(sink.clone());
+/// // ... run code with breakpoints ...
+/// reset_sink();
+/// let output = sink.contents();
+/// ```
+#[derive(Clone, Default)]
+pub struct MemorySink {
+    buffer: std::sync::Arc<Mutex<String>>,
+}
 
-                        if count >= 15 {
-                            eprintln!("\x1b[90m     ... (truncated)\x1b[0m");
-                            break;
-                        }
-                    }
-                }
-            }
-        }
-        if count >= 15 {
-            break;
-        }
+impl MemorySink {
+    /// An empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Everything written so far
+    pub fn contents(&self) -> String {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner).clone()
     }
 
-    eprintln!("\x1b[1;33m───────────────────\x1b[0m\n");
+    /// Everything written so far, clearing the buffer
+    pub fn take(&self) -> String {
+        std::mem::take(&mut *self.buffer.lock().unwrap_or_else(PoisonError::into_inner))
+    }
 }
 
-/// Copy text to clipboard using system commands
-fn copy_to_clipboard(text: &str) -> bool {
-    use std::process::{Command, Stdio};
-    use std::io::Write as IoWrite;
+impl Sink for MemorySink {
+    fn write_text(&mut self, text: &str) {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner).push_str(text);
+    }
+}
 
-    // Try different clipboard commands based on platform
+/// The installed sink; `None` means stderr
+static SINK: Mutex<Option<Box<dyn Sink>>> = Mutex::new(None);
+
+/// Send all breakpoint output to `sink` from now on
+pub fn set_sink(sink: impl Sink + 'static) {
+    *SINK.lock().unwrap_or_else(PoisonError::into_inner) = Some(Box::new(sink));
+}
+
+/// Go back to writing breakpoint output to stderr
+pub fn reset_sink() {
+    *SINK.lock().unwrap_or_else(PoisonError::into_inner) = None;
+}
+
+/// Write text to the installed sink
+fn sink_write(text: &str) {
+    match SINK.lock().unwrap_or_else(PoisonError::into_inner).as_mut() {
+        Some(sink) => sink.write_text(text),
+        None => StderrSink.write_text(text),
+    }
+}
+
+/// Write a print-break status message (`error`s in red) to the installed sink
+fn report(message: &str, error: bool) {
+    let c = Colors::get();
+    let color = if error { c.red } else { c.gray };
+    sink_write(&format!("{}print-break: {}{}\n", color, message, c.reset));
+}
+
+/// Whether a sink has been installed with [`set_sink`]
+fn sink_installed() -> bool {
+    SINK.lock().unwrap_or_else(PoisonError::into_inner).is_some()
+}
+
+/// Whether the installed sink is a terminal
+fn sink_is_terminal() -> bool {
+    match SINK.lock().unwrap_or_else(PoisonError::into_inner).as_ref() {
+        Some(sink) => sink.is_terminal(),
+        None => StderrSink.is_terminal(),
+    }
+}
+
+// ============================================================================
+// Console - where a breakpoint's frame and prompt go
+// ============================================================================
+
+/// Like `eprint!`, but to the breakpoint's [`Console`]
+macro_rules! cprint {
+    ($con:expr, $($arg:tt)*) => {
+        $con.print(&format!($($arg)*))
+    };
+}
+
+/// Like `eprintln!`, but to the breakpoint's [`Console`]
+macro_rules! cprintln {
+    ($con:expr) => {
+        $con.print("\n")
+    };
+    ($con:expr, $($arg:tt)*) => {
+        $con.print(&format!("{}\n", format_args!($($arg)*)))
+    };
+}
+
+/// Where a breakpoint's frame is shown and its commands are read from
+enum Console {
+    /// The terminal: for a pausing frame, the controlling terminal
+    /// (`/dev/tty`) for commands and, unless a [`Sink`] is installed, output.
+    /// Otherwise commands come from stdin and output goes to the sink (stderr
+    /// by default).
+    Terminal(Option<std::io::BufReader<std::fs::File>>),
+    /// A `print-break-attach` client on the `PRINT_BREAK_SOCKET` socket.
+    /// `None` once the client has gone away.
+    #[cfg(unix)]
+    Remote(Option<std::io::BufReader<UnixStream>>),
+}
+
+impl Console {
+    /// Pick the console for a breakpoint.
+    ///
+    /// With `PRINT_BREAK_SOCKET` set, a pausing breakpoint (`wait`) blocks
+    /// until a client attaches; non-pausing frames go to an already attached
+    /// client, or the sink if there is none, like `dbg!`.
+    fn open(wait: bool) -> Self {
+        #[cfg(unix)]
+        if let Some(path) = socket_path() {
+            if let Some(client) = take_remote_client(&path, wait) {
+                return Console::Remote(Some(client));
+            }
+        }
+        let tty = if wait { open_tty() } else { None };
+        Console::Terminal(tty.map(std::io::BufReader::new))
+    }
+
+    /// Write text as-is (no newline added)
+    fn print(&mut self, s: &str) {
+        use std::io::Write;
+
+        match self {
+            // Straight to the terminal, so redirecting stderr doesn't hide the frame
+            Console::Terminal(Some(tty)) if !sink_installed() => {
+                let _ = tty.get_mut().write_all(s.as_bytes());
+            }
+            // Commands' own messages have fixed colors; keep logs and CI output plain
+            Console::Terminal(_) if !sink_is_terminal() => sink_write(&strip_ansi_codes(s)),
+            Console::Terminal(_) => sink_write(s),
+            #[cfg(unix)]
+            Console::Remote(client) => {
+                if let Some(stream) = client {
+                    if stream.get_mut().write_all(s.as_bytes()).is_err() {
+                        *client = None;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Whether someone can answer the prompt
+    fn is_interactive(&self) -> bool {
+        match self {
+            Console::Terminal(_) => is_tty(),
+            #[cfg(unix)]
+            Console::Remote(client) => client.is_some(),
+        }
+    }
+
+    /// Read one command line. `None` on end of input or error.
+    fn read_line(&mut self) -> Option<String> {
+        use std::io::BufRead;
+
+        let mut buf = String::new();
+        match self {
+            // Read from the terminal itself so the program's stdin isn't consumed
+            Console::Terminal(Some(tty)) => {
+                #[cfg(unix)]
+                if let Ok(mut typed) = TYPED_AHEAD.lock() {
+                    buf = std::mem::take(&mut *typed);
+                }
+                tty.read_line(&mut buf).ok()?;
+            }
+            Console::Terminal(None) => {
+                std::io::stdin().lock().read_line(&mut buf).ok()?;
+            }
+            #[cfg(unix)]
+            Console::Remote(client) => {
+                let stream = client.as_mut()?;
+                if !matches!(stream.read_line(&mut buf), Ok(n) if n > 0) {
+                    *client = None;
+                    return None;
+                }
+            }
+        }
+        Some(buf)
+    }
+
+    /// Show `prompt` with a countdown until input starts or `secs` run out.
+    /// Returns false on timeout.
+    fn wait_for_typing(&mut self, prompt: &str, secs: u64) -> bool {
+        #[cfg(unix)]
+        if let Console::Remote(_) = self {
+            use std::io::BufRead;
+
+            cprint!(self, "{}\x1b[90m(auto-continue in {}s)\x1b[0m ", prompt, secs);
+            let Console::Remote(Some(stream)) = self else { return false };
+            let _ = stream.get_ref().set_read_timeout(Some(std::time::Duration::from_secs(secs)));
+            let typed = stream.fill_buf().is_ok();
+            let _ = stream.get_ref().set_read_timeout(None);
+            return typed;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::io::AsRawFd;
+
+            let fd = match self {
+                Console::Terminal(Some(tty)) => tty.get_ref().as_raw_fd(),
+                _ => std::io::stdin().as_raw_fd(),
+            };
+            wait_for_terminal_typing(fd, prompt, secs, &mut |s| self.print(s))
+        }
+        #[cfg(not(unix))]
+        wait_for_terminal_typing(prompt, secs, &mut |s| self.print(s))
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Console {
+    /// Keep a still-connected client attached for the next breakpoint
+    fn drop(&mut self) {
+        if let Console::Remote(client) = self {
+            REMOTE_ATTACHED.store(client.is_some(), Ordering::Relaxed);
+            if let (Some(client), Ok(mut remote)) = (client.take(), REMOTE.lock()) {
+                if let Some(remote) = remote.as_mut() {
+                    remote.client = Some(client);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Console {
+    /// If the terminal is in raw mode (a ratatui/crossterm style app), switch to
+    /// cooked mode and the main screen until the returned guard is dropped.
+    fn suspend_tui(&self) -> Option<SuspendedTui> {
+        match self {
+            Console::Terminal(_) if is_tty() => SuspendedTui::suspend(open_tty()?),
+            _ => None,
+        }
+    }
+}
+
+/// Terminal state of an application that was in raw mode when a breakpoint hit.
+/// Dropping it restores that state exactly.
+#[cfg(unix)]
+struct SuspendedTui {
+    tty: std::fs::File,
+    termios: libc::termios,
+    alt_screen: bool,
+    cursor_hidden: bool,
+}
+
+#[cfg(unix)]
+impl SuspendedTui {
+    fn suspend(tty: std::fs::File) -> Option<Self> {
+        use std::io::Write;
+        use std::os::unix::io::AsRawFd;
+
+        let fd = tty.as_raw_fd();
+        // SAFETY: termios is plain data, filled in by tcgetattr before use
+        let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(fd, &mut termios) } != 0 {
+            return None;
+        }
+        if termios.c_lflag & libc::ICANON != 0 && termios.c_lflag & libc::ECHO != 0 {
+            return None; // Already cooked - nothing to do
+        }
+
+        // Ask the terminal (DECRQM) while still in raw mode. A mode the
+        // terminal doesn't report is left alone, so restoring can't change it.
+        let alt_screen = query_private_mode(&tty, 1049) == Some(true);
+        let cursor_hidden = query_private_mode(&tty, 25) == Some(false);
+
+        let mut cooked = termios;
+        cooked.c_lflag |= libc::ICANON | libc::ECHO | libc::ECHOE | libc::ISIG | libc::IEXTEN;
+        cooked.c_iflag |= libc::ICRNL;
+        cooked.c_oflag |= libc::OPOST | libc::ONLCR;
+        unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, &cooked) };
+
+        let mut seq = String::new();
+        if alt_screen {
+            // Save the cursor and leave the alternate screen *without* clearing
+            // it (mode 47, unlike 1049), so the app's display is intact on return
+            seq.push_str("\x1b7\x1b[?47l\r\n");
+        }
+        if cursor_hidden {
+            seq.push_str("\x1b[?25h");
+        }
+        let mut out = &tty;
+        let _ = out.write_all(seq.as_bytes());
+
+        Some(Self { tty, termios, alt_screen, cursor_hidden })
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SuspendedTui {
+    fn drop(&mut self) {
+        use std::io::Write;
+        use std::os::unix::io::AsRawFd;
+
+        let mut seq = String::new();
+        if self.cursor_hidden {
+            seq.push_str("\x1b[?25l");
+        }
+        if self.alt_screen {
+            seq.push_str("\x1b[?47h\x1b8");
+        }
+        let mut out = &self.tty;
+        let _ = out.write_all(seq.as_bytes());
+        unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSADRAIN, &self.termios) };
+    }
+}
+
+/// Keys read from the terminal while waiting for a DECRQM reply, handed to
+/// the prompt so they aren't lost
+#[cfg(unix)]
+static TYPED_AHEAD: Mutex<String> = Mutex::new(String::new());
+
+/// Ask whether DEC private `mode` is set (DECRQM). Needs the terminal in raw
+/// mode; `None` if the terminal doesn't answer within 100ms.
+///
+/// Not asked while keys are waiting to be read, since they'd be mixed up with
+/// the reply.
+#[cfg(unix)]
+fn query_private_mode(tty: &std::fs::File, mode: u32) -> Option<bool> {
+    use std::io::{Read, Write};
+    use std::os::unix::io::AsRawFd;
+
+    let poll = |timeout| {
+        let mut pollfd = libc::pollfd { fd: tty.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+        unsafe { libc::poll(&mut pollfd, 1, timeout) > 0 }
+    };
+    if poll(0) {
+        return None;
+    }
+    let mut out = tty;
+    out.write_all(format!("\x1b[?{}$p", mode).as_bytes()).ok()?;
+
+    // Reply: ESC [ ? <mode> ; <status> $ y
+    let mut reply = Vec::new();
+    while !reply.ends_with(b"$y") && reply.len() < 32 {
+        if !poll(100) {
+            return None;
+        }
+        let mut byte = [0u8; 1];
+        let mut input = tty;
+        if input.read(&mut byte).ok()? == 0 {
+            return None;
+        }
+        let expected = match reply.len() {
+            0 => byte[0] == 0x1b,
+            1 => byte[0] == b'[',
+            2 => byte[0] == b'?',
+            _ => byte[0].is_ascii_digit() || matches!(byte[0], b';' | b'$' | b'y'),
+        };
+        if !expected {
+            // Typed before the reply arrived: keep it for the prompt
+            reply.push(byte[0]);
+            if let Ok(mut typed) = TYPED_AHEAD.lock() {
+                typed.push_str(&String::from_utf8_lossy(&reply));
+            }
+            return None;
+        }
+        reply.push(byte[0]);
+    }
+
+    let reply = String::from_utf8_lossy(&reply);
+    let status = reply.split(';').nth(1)?.trim_end_matches("$y");
+    // 1 = set, 3 = permanently set; 2 and 4 are the reset equivalents
+    Some(matches!(status, "1" | "3"))
+}
+
+/// Listening socket and attached client for `PRINT_BREAK_SOCKET`
+#[cfg(unix)]
+struct RemoteState {
+    listener: UnixListener,
+    client: Option<std::io::BufReader<UnixStream>>,
+    /// The socket file, removed again when the listener goes away
+    path: std::path::PathBuf,
+}
+
+#[cfg(unix)]
+impl Drop for RemoteState {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Created on the first breakpoint after `PRINT_BREAK_SOCKET` is set
+#[cfg(unix)]
+static REMOTE: Mutex<Option<RemoteState>> = Mutex::new(None);
+
+/// Set once binding the socket has failed, so the warning is only shown once
+#[cfg(unix)]
+static REMOTE_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Set while a `print-break-attach` client is connected
+#[cfg(unix)]
+static REMOTE_ATTACHED: AtomicBool = AtomicBool::new(false);
+
+/// Whether frames are going to an attached `print-break-attach` client
+fn remote_attached() -> bool {
+    #[cfg(unix)]
+    return REMOTE_ATTACHED.load(Ordering::Relaxed);
+    #[cfg(not(unix))]
+    return false;
+}
+
+/// Stop listening on `PRINT_BREAK_SOCKET` and remove the socket file
+#[cfg(unix)]
+fn close_remote() {
+    // Another thread may be blocked accepting a client; don't wait for it
+    let remote = match REMOTE.try_lock() {
+        Ok(remote) => Some(remote),
+        Err(std::sync::TryLockError::Poisoned(e)) => Some(e.into_inner()),
+        Err(std::sync::TryLockError::WouldBlock) => None,
+    };
+    if let Some(mut remote) = remote {
+        remote.take();
+    }
+}
+
+/// Registered with `atexit` so the socket doesn't outlive the process
+#[cfg(unix)]
+extern "C" fn close_remote_at_exit() {
+    close_remote();
+}
+
+/// Socket path from `PRINT_BREAK_SOCKET`, if set
+#[cfg(unix)]
+fn socket_path() -> Option<std::path::PathBuf> {
+    std::env::var_os("PRINT_BREAK_SOCKET")
+        .filter(|p| !p.is_empty())
+        .map(std::path::PathBuf::from)
+}
+
+/// Take the attached client, binding the socket and accepting a connection
+/// as needed. Only blocks for a connection when `wait` is set.
+#[cfg(unix)]
+fn take_remote_client(path: &std::path::Path, wait: bool) -> Option<std::io::BufReader<UnixStream>> {
+    use std::os::unix::fs::FileTypeExt;
+
+    if REMOTE_FAILED.load(Ordering::Relaxed) {
+        return None;
+    }
+    let mut remote = REMOTE.lock().ok()?;
+
+    if remote.is_none() {
+        // Replace a socket left behind by an earlier run
+        if std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_socket()) {
+            let _ = std::fs::remove_file(path);
+        }
+        match UnixListener::bind(path) {
+            Ok(listener) => {
+                *remote = Some(RemoteState { listener, client: None, path: path.to_path_buf() });
+                // SAFETY: registering a plain `extern "C"` function with no arguments
+                unsafe { libc::atexit(close_remote_at_exit) };
+            }
+            Err(e) => {
+                REMOTE_FAILED.store(true, Ordering::Relaxed);
+                report(&format!("can't listen on {}: {}", path.display(), e), true);
+                return None;
+            }
+        }
+    }
+    let state = remote.as_mut()?;
+
+    if let Some(client) = state.client.take() {
+        REMOTE_ATTACHED.store(true, Ordering::Relaxed);
+        return Some(client);
+    }
+
+    let stream = if wait {
+        report(&format!("waiting for `print-break-attach {}`...", path.display()), false);
+        state.listener.accept().ok()?.0
+    } else {
+        let _ = state.listener.set_nonblocking(true);
+        let accepted = state.listener.accept();
+        let _ = state.listener.set_nonblocking(false);
+        accepted.ok()?.0
+    };
+    let _ = stream.set_nonblocking(false);
+    REMOTE_ATTACHED.store(true, Ordering::Relaxed);
+    Some(std::io::BufReader::new(stream))
+}
+
+// ============================================================================
+// Scripted responses (PRINT_BREAK_SCRIPT)
+// ============================================================================
+
+/// Prompt commands read from the `PRINT_BREAK_SCRIPT` file
+#[derive(Default)]
+struct Script {
+    /// Commands for any breakpoint, in file order
+    commands: VecDeque<String>,
+    /// Commands for a single location (`file:line` prefixed lines), in file order
+    sites: Vec<(String, VecDeque<String>)>,
+}
+
+impl Script {
+    /// One command per line, `#` lines are comments. A line starting with
+    /// `file:line` (e.g. `src/main.rs:42 more`) is only used at that location.
+    fn parse(text: &str) -> Self {
+        let mut script = Script::default();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (first, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let is_site = first.rsplit_once(':').is_some_and(|(path, n)| !path.is_empty() && n.parse::<u32>().is_ok());
+            if !is_site {
+                script.commands.push_back(line.to_string());
+                continue;
+            }
+            let command = match rest.trim() {
+                "" => "continue".to_string(),
+                cmd => cmd.to_string(),
+            };
+            match script.sites.iter_mut().find(|(site, _)| site == first) {
+                Some((_, commands)) => commands.push_back(command),
+                None => script.sites.push((first.to_string(), VecDeque::from([command]))),
+            }
+        }
+        script
+    }
+
+    /// Next command for a breakpoint at `file:line`: the location's own
+    /// commands first, then the shared ones
+    fn next(&mut self, file: &str, line: u32) -> Option<String> {
+        let here = format!("{}:{}", file.replace('\\', "/"), line);
+        let site = self.sites.iter_mut().find(|(site, commands)| {
+            !commands.is_empty() && (here == *site || here.ends_with(&format!("/{}", site)))
+        });
+        match site {
+            Some((_, commands)) => commands.pop_front(),
+            None => self.commands.pop_front(),
+        }
+    }
+}
+
+/// The script from `PRINT_BREAK_SCRIPT`, loaded on first use
+fn script() -> Option<&'static Mutex<Script>> {
+    static SCRIPT: std::sync::OnceLock<Option<Mutex<Script>>> = std::sync::OnceLock::new();
+    SCRIPT.get_or_init(|| {
+        let path = std::env::var_os("PRINT_BREAK_SCRIPT").filter(|p| !p.is_empty())?;
+        match std::fs::read_to_string(&path) {
+            Ok(text) => Some(Mutex::new(Script::parse(&text))),
+            Err(e) => {
+                report(&format!("can't read PRINT_BREAK_SCRIPT {}: {}", path.to_string_lossy(), e), true);
+                None
+            }
+        }
+    }).as_ref()
+}
+
+/// Source of a command taken from `PRINT_BREAK_SCRIPT_DEFAULT`
+const SCRIPT_DEFAULT: &str = "script default";
+
+/// The scripted command for a breakpoint at `file:line`, with where it came from.
+///
+/// Once the script runs out, `PRINT_BREAK_SCRIPT_DEFAULT` is used (`continue`
+/// if unset); `prompt` hands control back to the terminal instead.
+fn next_scripted_command(file: &str, line: u32) -> Option<(String, &'static str)> {
+    let script = script()?;
+    let next = script.lock().unwrap_or_else(PoisonError::into_inner).next(file, line);
+    if let Some(command) = next {
+        return Some((command, "script"));
+    }
+    let default = std::env::var("PRINT_BREAK_SCRIPT_DEFAULT")
+        .ok()
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "continue".to_string());
+    if default == "prompt" {
+        return None;
+    }
+    Some((default, SCRIPT_DEFAULT))
+}
+
+/// Stored full output for "show more" functionality
+static LAST_FULL_OUTPUT: std::sync::Mutex<Option<String>> = std::sync::Mutex::new(None);
+
+/// Store full output for potential "show more"
+#[doc(hidden)]
+pub fn store_full_output(output: String) {
+    if let Ok(mut guard) = LAST_FULL_OUTPUT.lock() {
+        *guard = Some(output);
+    }
+}
+
+/// A variable captured at a breakpoint, already formatted for display
+#[doc(hidden)]
+pub struct Var {
+    pub name: &'static str,
+    /// Truncated, colorized rendering shown in the frame
+    pub formatted: String,
+    /// Untruncated rendering used by "show more" and copy
+    pub full: String,
+    /// Uncolored text the renderings were made from. `full` is depth-collapsed
+    /// in a terminal, so change detection, logs, exports and captures use this
+    /// instead, and the `x` command re-renders parts of it
+    pub source: String,
+    /// `source` is JSON from `serde`, so `c` copies it as JSON
+    pub json: bool,
+}
+
+impl Var {
+    pub fn new<T: Debug + ?Sized>(name: &'static str, value: &T) -> Self {
+        let full = format_value_full(value);
+        let debug = format!("{:#?}", value);
+        // Strings are shown as their (possibly JSON/TOML/YAML) contents
+        let source = if debug.starts_with('"') { strip_ansi_codes(&full) } else { debug };
+        Self {
+            name,
+            formatted: format_value(value),
+            full,
+            source,
+            json: false,
+        }
+    }
+
+    /// A variable rendered through `serde` as JSON: depth-collapsed in the
+    /// frame, complete (and valid JSON) for "show more" and copy
+    #[cfg(feature = "serde")]
+    pub fn serde<T: serde::Serialize + ?Sized>(name: &'static str, value: &T) -> Self {
+        let json = match serde_json::to_value(value) {
+            Ok(json) => json,
+            Err(e) => return Self::custom(name, format!("(can't serialize: {})", e)),
+        };
+        let mut collapsed = String::new();
+        json_collapsed(&json, 0, max_depth(), &mut collapsed);
+        let full = serde_json::to_string_pretty(&json).unwrap_or(collapsed.clone());
+        Self {
+            name,
+            formatted: truncate_output(&colorize_json(&collapsed)),
+            full: colorize_json(&full),
+            source: full,
+            json: true,
+        }
+    }
+
+    /// A variable rendered by a custom formatter instead of `Debug`
+    pub fn custom(name: &'static str, text: String) -> Self {
+        let full = colorize_debug(&text);
+        Self {
+            name,
+            formatted: truncate_output(&full),
+            full,
+            source: text,
+            json: false,
+        }
+    }
+}
+
+// ============================================================================
+// Custom formatters
+// ============================================================================
+
+/// Custom rendering for breakpoint values, used instead of `Debug`.
+///
+/// The breakpoint macros pick this up automatically for any type that
+/// implements it (the type doesn't need `Debug` at all). The output is still
+/// colorized, truncated, and available to the `m` and `c` commands.
+///
+/// ```rust,no_run
+/// use print_break::{print_break, BreakFormat};
+///
+/// struct Money { cents: i64 }
+///
+/// impl BreakFormat for Money {
+///     fn break_format(&self) -> String {
+///         format!("${}.{:02}", self.cents / 100, self.cents % 100)
+///     }
+/// }
+///
+/// let price = Money { cents: 1250 };
+/// print_break!(price);  // price = $12.50
+/// ```
+pub trait BreakFormat {
+    fn break_format(&self) -> String;
+}
+
+impl<T: BreakFormat + ?Sized> BreakFormat for &T {
+    fn break_format(&self) -> String {
+        (**self).break_format()
+    }
+}
+
+impl<T: BreakFormat + ?Sized> BreakFormat for &mut T {
+    fn break_format(&self) -> String {
+        (**self).break_format()
+    }
+}
+
+impl<T: BreakFormat + ?Sized> BreakFormat for Box<T> {
+    fn break_format(&self) -> String {
+        (**self).break_format()
+    }
+}
+
+/// A renderer registered with [`register_formatter`], downcasting to its type
+type Formatter = Box<dyn Fn(&dyn Any) -> String + Send + Sync>;
+
+/// Registered renderers, keyed by the type they render
+static FORMATTERS: RwLock<BTreeMap<TypeId, Formatter>> = RwLock::new(BTreeMap::new());
+
+/// Render values of type `T` with `render` instead of `Debug`.
+///
+/// This works for types from other crates, which can't implement
+/// [`BreakFormat`]. Only owned `'static` values are looked up: references and
+/// values that borrow are shown with `Debug`.
+///
+/// ```rust,no_run
+/// use print_break::register_formatter;
+///
+/// register_formatter::<std::time::Duration>(|d: &std::time::Duration| format!("{:.3?}", d));
+/// ```
+pub fn register_formatter<T: 'static>(render: impl Fn(&T) -> String + Send + Sync + 'static) {
+    FORMATTERS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .insert(TypeId::of::<T>(), Box::new(move |value| value.downcast_ref::<T>().map(&render).unwrap_or_default()));
+}
+
+/// Go back to `Debug` rendering for values of type `T`
+pub fn unregister_formatter<T: 'static>() {
+    FORMATTERS
+        .write()
+        .unwrap_or_else(PoisonError::into_inner)
+        .remove(&TypeId::of::<T>());
+}
+
+/// Render `value` with its registered formatter, if there is one
+fn registered_format<T: Any>(value: &T) -> Option<String> {
+    let formatters = FORMATTERS.read().unwrap_or_else(PoisonError::into_inner);
+    formatters.get(&TypeId::of::<T>()).map(|render| render(value))
+}
+
+/// Wraps a breakpoint value so the macros can prefer [`BreakFormat`] and
+/// registered formatters over `Debug`
+#[doc(hidden)]
+pub struct VarRef<'a, T: ?Sized>(pub &'a T);
+
+/// Picked by method resolution when the value implements [`BreakFormat`]
+#[doc(hidden)]
+pub trait BreakFormatVar {
+    fn __print_break_var(&self, name: &'static str) -> Var;
+}
+
+impl<T: BreakFormat + ?Sized> BreakFormatVar for &&&VarRef<'_, T> {
+    fn __print_break_var(&self, name: &'static str) -> Var {
+        Var::custom(name, self.0.break_format())
+    }
+}
+
+/// References are shown with `Debug`, so they never reach [`RegisteredVar`]'s
+/// `'static` bound
+#[doc(hidden)]
+pub trait RefVar {
+    fn __print_break_var(&self, name: &'static str) -> Var;
+}
+
+impl<T: Debug + ?Sized> RefVar for &&VarRef<'_, &T> {
+    fn __print_break_var(&self, name: &'static str) -> Var {
+        Var::new(name, *self.0)
+    }
+}
+
+impl<T: Debug + ?Sized> RefVar for &&VarRef<'_, &mut T> {
+    fn __print_break_var(&self, name: &'static str) -> Var {
+        Var::new(name, &**self.0)
+    }
+}
+
+/// Picked for owned `'static` values, which can have a registered formatter
+#[doc(hidden)]
+pub trait RegisteredVar {
+    fn __print_break_var(&self, name: &'static str) -> Var;
+}
+
+impl<T: Any + Debug> RegisteredVar for &VarRef<'_, T> {
+    fn __print_break_var(&self, name: &'static str) -> Var {
+        match registered_format(self.0) {
+            Some(text) => Var::custom(name, text),
+            None => Var::new(name, self.0),
+        }
+    }
+}
+
+/// The `Debug` fallback, one auto-ref further away than [`RegisteredVar`]
+#[doc(hidden)]
+pub trait DebugVar {
+    fn __print_break_var(&self, name: &'static str) -> Var;
+}
+
+impl<T: Debug + ?Sized> DebugVar for VarRef<'_, T> {
+    fn __print_break_var(&self, name: &'static str) -> Var {
+        Var::new(name, self.0)
+    }
+}
+
+/// Width of the frame borders
+const FRAME_WIDTH: usize = 50;
+
+/// Build the `BREAK #N` (or `TRACE #N`) header line, padded to the frame width.
+///
+/// `note` is extra plain text shown after the id, e.g. `hit 3`.
+fn frame_header(label: &str, break_id: usize, note: &str, elapsed: Option<std::time::Duration>, border: &BorderStyle, c: &Colors) -> String {
+    let elapsed_str = elapsed.map(format_elapsed).unwrap_or_default();
+    let note_str = if note.is_empty() {
+        String::new()
+    } else {
+        format!(" {}({}){}", c.gray, note, c.reset)
+    };
+    let title = format!(" {} #{}{}{} ", label, break_id, note_str, elapsed_str);
+    let visible = strip_ansi_codes(&title).chars().count();
+    let h = border.horizontal.to_string();
+    let fill = h.repeat((FRAME_WIDTH + 1).saturating_sub(2 + visible));
+    format!("{}{}{}{}{}{}{}", c.yellow, border.top_left, h, title, c.yellow, fill, c.reset)
+}
+
+/// Header note for a location's hit count (omitted on the first hit)
+fn hit_note(hit: usize) -> String {
+    if hit > 1 { format!("hit {}", hit) } else { String::new() }
+}
+
+/// Lines of source shown above and below the breakpoint in every frame
+/// (`PRINT_BREAK_CONTEXT`, off by default)
+fn frame_context() -> usize {
+    std::env::var("PRINT_BREAK_CONTEXT")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+        .min(MAX_CONTEXT)
+}
+
+/// Most source lines shown on either side of a breakpoint
+const MAX_CONTEXT: usize = 1000;
+
+/// The lines of `source` within `context` of `line`, numbered, with the
+/// breakpoint line marked and highlighted
+fn render_snippet(source: &str, line: u32, context: usize, c: &Colors) -> Vec<String> {
+    let line = line as usize;
+    let first = line.saturating_sub(context).max(1);
+    let last = line.saturating_add(context);
+    let width = last.to_string().len();
+    source
+        .lines()
+        .enumerate()
+        .map(|(i, text)| (i + 1, text.replace('\t', "    ")))
+        .filter(|(n, _)| (first..=last).contains(n))
+        .map(|(n, text)| {
+            if n == line {
+                format!("{}> {:>width$} │{} {}{}{}", c.yellow, n, c.reset, c.white, text, c.reset, width = width)
+            } else {
+                format!("{}  {:>width$} │{} {}", c.gray, n, c.reset, text, width = width)
+            }
+        })
+        .collect()
+}
+
+/// The source around `file:line`, if the file can be found on disk
+fn source_snippet(file: &'static str, line: u32, context: usize, c: &Colors) -> Option<Vec<String>> {
+    let source = std::fs::read_to_string(source_path(file, line)?).ok()?;
+    let snippet = render_snippet(&source, line, context, c);
+    (!snippet.is_empty()).then_some(snippet)
+}
+
+/// Print a breakpoint frame for `file:line` and wait for input.
+///
+/// `hit` is the location's hit count from [`record_hit`].
+#[doc(hidden)]
+pub fn show_break(file: &'static str, line: u32, hit: usize, vars: &[Var]) {
+    show_frame(file, line, hit, &hit_note(hit), vars, None, !is_trace_mode());
+}
+
+/// Print a breakpoint frame for `file:line` without pausing (`print_trace!`).
+#[doc(hidden)]
+pub fn show_trace(file: &'static str, line: u32, hit: usize, vars: &[Var]) {
+    show_frame(file, line, hit, &hit_note(hit), vars, None, false);
+}
+
+/// Show the frame only if the variables differ from the previous hit at `file:line`.
+///
+/// The first hit records a baseline; unchanged hits are counted silently and the
+/// count is shown in the header of the next changed frame.
+#[doc(hidden)]
+pub fn show_break_if_changed(file: &'static str, line: u32, hit: usize, vars: &[Var]) {
+    let current: Vec<String> = vars.iter().map(|v| v.source.clone()).collect();
+
+    let (previous, unchanged) = {
+        let Ok(mut sites) = SITES.lock() else { return };
+        let site = sites.entry((file, line)).or_default();
+        match site.last_values.replace(current.clone()) {
+            Some(previous) if previous != current => (previous, std::mem::take(&mut site.unchanged)),
+            Some(_) => {
+                site.unchanged += 1;
+                return;
+            }
+            None => return,
+        }
+    };
+
+    let mut note = hit_note(hit);
+    if unchanged > 0 {
+        note = format!("{}, {} unchanged", note, unchanged);
+    }
+    show_frame(file, line, hit, &note, vars, Some(&previous), !is_trace_mode());
+}
+
+/// Render old and new values in two columns, old in red and new in green
+fn side_by_side(old: &str, new: &str, c: &Colors) -> Vec<String> {
+    const COLUMN: usize = 40;
+
+    let old_clean = strip_ansi_codes(old);
+    let new_clean = strip_ansi_codes(new);
+    let old_lines: Vec<&str> = old_clean.lines().collect();
+    let new_lines: Vec<&str> = new_clean.lines().collect();
+
+    let fit = |s: &str| -> String {
+        if s.chars().count() > COLUMN {
+            let cut: String = s.chars().take(COLUMN - 1).collect();
+            format!("{}…", cut)
+        } else {
+            format!("{:<width$}", s, width = COLUMN)
+        }
+    };
+
+    let mut rows = vec![format!("{}{} │ {}{}", c.gray, fit("old"), "new", c.reset)];
+    for i in 0..old_lines.len().max(new_lines.len()) {
+        let left = fit(old_lines.get(i).copied().unwrap_or(""));
+        let right = new_lines.get(i).copied().unwrap_or("");
+        rows.push(format!("{}{}{} {}│{} {}{}{}", c.red, left, c.reset, c.gray, c.reset, c.green, right, c.reset));
+    }
+    rows
+}
+
+/// Lines of unchanged context kept around each change in a diff
+const DIFF_CONTEXT: usize = 2;
+
+/// Largest old × new line count compared line by line; bigger changes are
+/// shown as a whole block removed and added
+const MAX_DIFF_CELLS: usize = 1_000_000;
+
+/// Line diff of `old` against `new`: `' '` for kept lines, `'-'` removed, `'+'` added
+fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<(char, &'a str)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (a, b) = (&old[prefix..old.len() - suffix], &new[prefix..new.len() - suffix]);
+
+    let mut out: Vec<(char, &str)> = old[..prefix].iter().map(|l| (' ', *l)).collect();
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        out.extend(a.iter().map(|l| ('-', *l)));
+        out.extend(b.iter().map(|l| ('+', *l)));
+    } else {
+        // lcs[i][j] = longest common subsequence of a[i..] and b[j..]
+        let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for i in (0..a.len()).rev() {
+            for j in (0..b.len()).rev() {
+                lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+            }
+        }
+        let (mut i, mut j) = (0, 0);
+        while i < a.len() || j < b.len() {
+            if i < a.len() && j < b.len() && a[i] == b[j] {
+                out.push((' ', a[i]));
+                (i, j) = (i + 1, j + 1);
+            } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+                out.push(('-', a[i]));
+                i += 1;
+            } else {
+                out.push(('+', b[j]));
+                j += 1;
+            }
+        }
+    }
+    out.extend(old[old.len() - suffix..].iter().map(|l| (' ', *l)));
+    out
+}
+
+/// Colored diff rows for `old` → `new`, only the changes and their context.
+/// Empty when nothing changed.
+fn diff_rows(old: &str, new: &str, c: &Colors) -> Vec<String> {
+    let lines = diff_lines(old, new);
+    let mut keep = vec![false; lines.len()];
+    for (i, _) in lines.iter().enumerate().filter(|(_, (tag, _))| *tag != ' ') {
+        let end = (i + DIFF_CONTEXT + 1).min(lines.len());
+        keep[i.saturating_sub(DIFF_CONTEXT)..end].iter_mut().for_each(|k| *k = true);
+    }
+    if !lines.iter().any(|(tag, _)| *tag != ' ') {
+        return Vec::new();
+    }
+
+    let mut rows = Vec::new();
+    let mut skipped = false;
+    for ((tag, text), keep) in lines.iter().zip(keep) {
+        if !keep {
+            skipped = true;
+            continue;
+        }
+        if std::mem::take(&mut skipped) {
+            rows.push(format!("{}  ⋮{}", c.gray, c.reset));
+        }
+        rows.push(match tag {
+            '-' => format!("{}- {}{}", c.red, text, c.reset),
+            '+' => format!("{}+ {}{}", c.green, text, c.reset),
+            _ => format!("{}  {}{}", c.gray, text, c.reset),
+        });
+    }
+    if skipped {
+        rows.push(format!("{}  ⋮{}", c.gray, c.reset));
+    }
+    rows
+}
+
+/// Remember the values shown at `file:line` and return the previous frame's
+fn swap_last_frame(file: &'static str, line: u32, hit: usize, vars: &[Var]) -> Option<LastFrame> {
+    let values = vars.iter().map(|v| (v.name, v.source.clone())).collect();
+    let mut sites = SITES.lock().ok()?;
+    sites.entry((file, line)).or_default().last_frame.replace(LastFrame { hit, values })
+}
+
+/// Whether every frame shows what changed since the location's last frame
+/// (`PRINT_BREAK_DIFF=1`)
+fn diff_in_frame() -> bool {
+    std::env::var("PRINT_BREAK_DIFF").is_ok_and(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+}
+
+/// Everything written to the `PRINT_BREAK_LOG` file for one breakpoint hit
+struct LogRecord<'a> {
+    break_id: usize,
+    file: &'static str,
+    line: u32,
+    hit: usize,
+    elapsed: Option<std::time::Duration>,
+    thread: String,
+    vars: &'a [Var],
+}
+
+/// Set once a log write has failed, so the warning is only shown once
+static LOG_FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Append a JSON Lines record for this hit to the `PRINT_BREAK_LOG` file, if set
+fn log_break(record: &LogRecord) {
+    use std::io::Write;
+
+    let Some(path) = std::env::var_os("PRINT_BREAK_LOG").filter(|p| !p.is_empty()) else {
+        return;
+    };
+
+    let vars: Vec<String> = record
+        .vars
+        .iter()
+        .map(|var| {
+            format!(
+                "{{\"name\":{},\"value\":{}}}",
+                json_string(var.name),
+                json_string(&var.source)
+            )
+        })
+        .collect();
+    let elapsed = record
+        .elapsed
+        .map(|d| format!("{}", d.as_secs_f64()))
+        .unwrap_or_else(|| "null".to_string());
+    let line = format!(
+        "{{\"id\":{},\"file\":{},\"line\":{},\"hit\":{},\"elapsed\":{},\"thread\":{},\"vars\":[{}]}}\n",
+        record.break_id,
+        json_string(record.file),
+        record.line,
+        record.hit,
+        elapsed,
+        json_string(&record.thread),
+        vars.join(",")
+    );
+
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        if !LOG_FAILED.swap(true, Ordering::Relaxed) {
+            report(&format!("can't write PRINT_BREAK_LOG {}: {}", path.to_string_lossy(), e), true);
+        }
+    }
+}
+
+/// Quote and escape a string as a JSON string literal
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+// ============================================================================
+// Capture (for tests)
+// ============================================================================
+
+/// A breakpoint hit recorded by [`capture`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BreakEvent {
+    pub file: &'static str,
+    pub line: u32,
+    /// How many times this location had been reached
+    pub hit: usize,
+    /// False for trace frames (`print_trace!` or `PRINT_BREAK=trace`)
+    pub paused: bool,
+    pub vars: Vec<CapturedVar>,
+}
+
+/// A variable as a breakpoint would show it, with colors removed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedVar {
+    pub name: &'static str,
+    /// The full (untruncated) rendering, the same with or without a terminal
+    pub value: String,
+}
+
+impl BreakEvent {
+    /// The rendered value of the variable called `name`
+    pub fn var(&self, name: &str) -> Option<&str> {
+        self.vars.iter().find(|v| v.name == name).map(|v| v.value.as_str())
+    }
+}
+
+thread_local! {
+    /// Events of the innermost [`capture`] running on this thread
+    static CAPTURED: std::cell::RefCell<Option<Vec<BreakEvent>>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Run `f` and return the breakpoints it hit instead of showing them.
+///
+/// Each breakpoint is recorded and continues right away, without output or
+/// prompt, regardless of `PRINT_BREAK`, skipped or ignored locations. Only
+/// breakpoints on the calling thread are captured, and like the macros
+/// themselves this captures nothing in release builds without the `release`
+/// feature.
+///
+/// ```rust,no_run
+/// use print_break::{capture, print_break};
+///
+/// let events = capture(|| {
+///     let total = 40 + 2;
+///     print_break!(total);
+/// });
+/// assert_eq!(events.len(), 1);
+/// assert_eq!(events[0].var("total"), Some("42"));
+/// ```
+pub fn capture(f: impl FnOnce()) -> Vec<BreakEvent> {
+    /// Puts back an enclosing capture, even if `f` panics
+    struct Restore(Option<Vec<BreakEvent>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let outer = self.0.take();
+            CAPTURED.with(|c| *c.borrow_mut() = outer);
+        }
+    }
+
+    let _restore = Restore(CAPTURED.with(|c| c.replace(Some(Vec::new()))));
+    f();
+    CAPTURED.with(|c| c.borrow_mut().take()).unwrap_or_default()
+}
+
+/// Whether a [`capture`] is running on this thread
+fn is_capturing() -> bool {
+    CAPTURED.with(|c| c.borrow().is_some())
+}
+
+/// Record the frame if a [`capture`] is running. Returns true if it was.
+fn capture_frame(file: &'static str, line: u32, hit: usize, vars: &[Var], paused: bool) -> bool {
+    CAPTURED.with(|c| match c.borrow_mut().as_mut() {
+        Some(events) => {
+            let vars = vars.iter().map(|v| CapturedVar { name: v.name, value: v.source.clone() }).collect();
+            events.push(BreakEvent { file, line, hit, paused, vars });
+            true
+        }
+        None => false,
+    })
+}
+
+/// Print the frame for `file:line`, then wait for input if `pause` is set.
+///
+/// When `previous` holds the variables' values from an earlier hit,
+/// changed variables are shown side by side with their old value.
+fn show_frame(file: &'static str, line: u32, hit: usize, note: &str, vars: &[Var], previous: Option<&[String]>, pause: bool) {
+    if capture_frame(file, line, hit, vars, pause) {
+        return;
+    }
+
+    let _guard = lock_breakpoint();
+    // Skip or ignore may have been chosen while this thread was queued
+    if !is_enabled_at(file, line) {
+        return;
+    }
+
+    let break_id = next_break_id();
+    let border = get_border_style();
+    let c = Colors::get();
+    let elapsed = get_elapsed();
+    let label = if pause { "BREAK" } else { "TRACE" };
+    let header = frame_header(label, break_id, note, elapsed, &border, &c);
+    update_break_time();
+
+    log_break(&LogRecord { break_id, file, line, hit, elapsed, thread: thread_label(), vars });
+    let last_frame = swap_last_frame(file, line, hit, vars);
+
+    let mut con = Console::open(pause);
+    // A raw-mode TUI gets the normal screen back until the frame is done
+    #[cfg(unix)]
+    let _tui = if pause { con.suspend_tui() } else { None };
+
+    let h = border.horizontal.to_string();
+
+    cprintln!(con);
+    cprintln!(con, "{}", header);
+    cprintln!(con, "{}{}{} {}{}:{}{} {}[{}]{}", c.yellow, border.vertical, c.reset, c.cyan, file, line, c.reset, c.gray, thread_label(), c.reset);
+
+    let context = frame_context();
+    if context > 0 {
+        // Quietly left out when the source isn't on disk
+        if let Some(snippet) = source_snippet(file, line, context, &c) {
+            cprintln!(con, "{}{}{}{}", c.yellow, border.tee_right, h.repeat(FRAME_WIDTH), c.reset);
+            for row in snippet {
+                cprintln!(con, "{}{}{} {}", c.yellow, border.vertical, c.reset, row);
+            }
+        }
+    }
+
+    if !vars.is_empty() {
+        // Collect full output for "more" option
+        let mut full_output = String::new();
+
+        cprintln!(con, "{}{}{}{}", c.yellow, border.tee_right, h.repeat(FRAME_WIDTH), c.reset);
+        for (i, var) in vars.iter().enumerate() {
+            full_output.push_str(&format!("{} = {}\n\n", var.name, var.full));
+
+            let old = previous.and_then(|p| p.get(i)).filter(|old| **old != var.source);
+            if let Some(old) = old {
+                cprintln!(con, "{}{}{} {}{}{} {}(changed){}", c.yellow, border.vertical, c.reset, c.green, var.name, c.reset, c.gray, c.reset);
+                for row in side_by_side(old, &var.source, &c) {
+                    cprintln!(con, "{}{}{} {}", c.yellow, border.vertical, c.reset, row);
+                }
+            } else if var.formatted.contains('\n') {
+                cprintln!(con, "{}{}{} {}{}{}=", c.yellow, border.vertical, c.reset, c.green, var.name, c.reset);
+                for line in var.formatted.lines() {
+                    cprintln!(con, "{}{}{} {}{}{}", c.yellow, border.vertical, c.reset, c.white, line, c.reset);
+                }
+            } else {
+                cprintln!(con, "{}{}{} {}{}{} = {}{}{}", c.yellow, border.vertical, c.reset, c.green, var.name, c.reset, c.white, var.formatted, c.reset);
+            }
+        }
+        store_full_output(full_output);
+    }
+
+    if let Some(last) = last_frame.as_ref().filter(|_| diff_in_frame()) {
+        let mut changes = Vec::new();
+        for var in vars {
+            let Some((_, old)) = last.values.iter().find(|(name, _)| *name == var.name) else { continue };
+            let rows = diff_rows(old, &var.source, &c);
+            if !rows.is_empty() {
+                changes.push(format!("{}{}{}", c.green, var.name, c.reset));
+                changes.extend(rows);
+            }
+        }
+        if !changes.is_empty() {
+            cprintln!(con, "{}{}{}{}", c.yellow, border.tee_right, h.repeat(FRAME_WIDTH), c.reset);
+            cprintln!(con, "{}{}{} {}changed since hit {}:{}", c.yellow, border.vertical, c.reset, c.gray, last.hit, c.reset);
+            for row in changes {
+                cprintln!(con, "{}{}{} {}", c.yellow, border.vertical, c.reset, row);
+            }
+        }
+    }
+
+    cprintln!(con, "{}{}{}{}", c.yellow, border.bottom_left, h.repeat(FRAME_WIDTH), c.reset);
+    if pause {
+        handle_input(&mut con, file, line, vars, last_frame.as_ref());
+    }
+}
+
+/// Show help menu
+fn show_help(con: &mut Console) {
+    cprintln!(con, "\n\x1b[1;33m─── print-break Help ───\x1b[0m");
+    cprintln!(con, "\x1b[36mEnter\x1b[0m     Continue to next breakpoint");
+    cprintln!(con, "\x1b[36mm\x1b[0m         Show full output (if truncated)");
+    cprintln!(con, "\x1b[36mp\x1b[0m         Open full output in $PAGER (default: less -R)");
+    cprintln!(con, "\x1b[36mt\x1b[0m         Show stack trace");
+    cprintln!(con, "\x1b[36md\x1b[0m         Diff values against the last hit here");
+    cprintln!(con, "\x1b[36ml [N]\x1b[0m     List source around this line (N lines each side)");
+    cprintln!(con, "\x1b[36me\x1b[0m         Open this line in $VISUAL / $EDITOR");
+    cprintln!(con, "\x1b[36mc\x1b[0m         Copy last value to clipboard");
+    cprintln!(con, "\x1b[36mw PATH [V]\x1b[0m Write values (or just V) to a file, JSON for .json");
+    cprintln!(con, "\x1b[36mx PATH [N]\x1b[0m Expand a value, e.g. x user.roles[2] (N = depth)");
+    cprintln!(con, "\x1b[36m/TEXT\x1b[0m     Search all values, collapsed parts included");
+    cprintln!(con, "\x1b[36mi\x1b[0m         Ignore this breakpoint location");
+    cprintln!(con, "\x1b[36mb\x1b[0m         List ignored breakpoint locations");
+    cprintln!(con, "\x1b[36mu N\x1b[0m       Re-enable ignored location N (u all = every one)");
+    cprintln!(con, "\x1b[36ms\x1b[0m         Skip all remaining breakpoints");
+    cprintln!(con, "\x1b[36mq\x1b[0m         Quit the program");
+    cprintln!(con, "\x1b[36mh / ?\x1b[0m     Show this help");
+    cprintln!(con);
+    cprintln!(con, "\x1b[90mEnvironment variables:\x1b[0m");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK=0\x1b[0m          Disable all breakpoints");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK=trace\x1b[0m      Print frames without pausing");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_DEPTH=N\x1b[0m    Max nesting depth (default: 4)");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_STYLE=X\x1b[0m    Border style: rounded, sharp, double, ascii");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_LOG=path\x1b[0m   Append a JSON Lines record per breakpoint hit");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_TIMEOUT=N\x1b[0m  Auto-continue after N seconds without input");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_CONTEXT=N\x1b[0m  Show N source lines around each breakpoint");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_DIFF=1\x1b[0m     Show changes since the last hit in each frame");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_SOCKET=path\x1b[0m Control breakpoints with print-break-attach");
+    cprintln!(con, "  \x1b[36mPRINT_BREAK_SCRIPT=path\x1b[0m Answer prompts with commands from a file");
+    cprintln!(con, "\x1b[1;33m─────────────────────────\x1b[0m\n");
+}
+
+/// Show stack trace
+#[cfg(feature = "backtrace")]
+fn show_stack_trace(con: &mut Console) {
+    cprintln!(con, "\n\x1b[1;33m─── Stack Trace ───\x1b[0m");
+
+    let bt = backtrace::Backtrace::new();
+    let mut in_relevant = false;
+    let mut count = 0;
+
+    for frame in bt.frames() {
+        for symbol in frame.symbols() {
+            if let Some(name) = symbol.name() {
+                let name_str = name.to_string();
+
+                // Skip internal frames
+                if name_str.contains("print_break::") || name_str.contains("backtrace::") {
+                    continue;
+                }
+
+                // Start showing after we exit print_break internals
+                if !in_relevant && !name_str.contains("print_break") {
+                    in_relevant = true;
+                }
+
+                if in_relevant {
+                    let file = symbol.filename()
+                        .map(|p| p.display().to_string())
+                        .unwrap_or_default();
+                    let line = symbol.lineno().unwrap_or(0);
+
+                    // Simplify long paths
+                    let short_file = file.rsplit('/').next().unwrap_or(&file);
+
+                    if !name_str.contains("std::") && !name_str.contains("core::") && !name_str.contains("__rust") {
+                        cprintln!(con, "\x1b[90m{:>3}.\x1b[0m \x1b[36m{}\x1b[0m", count, name_str);
+                        if !file.is_empty() && line > 0 {
+                            cprintln!(con, "      \x1b[90mat {}:{}\x1b[0m", short_file, line);
+                        }
+                        count += 1;
+
+                        if count >= 15 {
+                            cprintln!(con, "\x1b[90m     ... (truncated)\x1b[0m");
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        if count >= 15 {
+            break;
+        }
+    }
+
+    cprintln!(con, "\x1b[1;33m───────────────────\x1b[0m\n");
+}
+
+/// Stack traces need the `backtrace` feature
+#[cfg(not(feature = "backtrace"))]
+fn show_stack_trace(con: &mut Console) {
+    cprintln!(con, "\x1b[90m(stack trace unavailable - enable the `backtrace` feature)\x1b[0m");
+}
+
+/// Copy text to clipboard using system commands
+fn copy_to_clipboard(text: &str) -> bool {
+    use std::process::{Command, Stdio};
+    use std::io::Write as IoWrite;
+
+    // Try different clipboard commands based on platform
     let commands = if cfg!(target_os = "macos") {
         vec![("pbcopy", vec![])]
     } else if cfg!(target_os = "windows") {
@@ -922,72 +2928,581 @@ fn copy_to_clipboard(text: &str) -> bool {
     false
 }
 
-/// Handle user input at breakpoint. Returns true if should continue, false if should quit.
-#[doc(hidden)]
-pub fn handle_input() -> bool {
-    use std::io::{self, BufRead, Write};
+/// Print the stored full output
+fn show_full_output(con: &mut Console) {
+    if let Ok(guard) = LAST_FULL_OUTPUT.lock() {
+        if let Some(ref full) = *guard {
+            cprintln!(con, "\n\x1b[1;33m─── Full Output ───\x1b[0m");
+            for line in full.lines() {
+                cprintln!(con, "\x1b[37m{}\x1b[0m", line);
+            }
+            cprintln!(con, "\x1b[1;33m───────────────────\x1b[0m\n");
+        } else {
+            cprintln!(con, "\x1b[90m(no truncated output to show)\x1b[0m");
+        }
+    }
+}
+
+/// Show `text` in `$PAGER` (default `less -R`) and wait for it to exit.
+///
+/// Returns false when the console isn't a local terminal or the pager can't
+/// be started, so the caller can fall back to printing.
+fn page_output(con: &Console, text: &str) -> bool {
+    use std::process::{Command, Stdio};
+    use std::io::Write as IoWrite;
+
+    // A log file or buffer sink gets the text instead
+    if !matches!(con, Console::Terminal(_)) || !is_tty() || (sink_installed() && !sink_is_terminal()) {
+        return false;
+    }
+    let pager = std::env::var("PAGER")
+        .ok()
+        .filter(|p| !p.trim().is_empty())
+        .unwrap_or_else(|| "less -R".to_string());
+    let mut parts = pager.split_whitespace();
+    let Some(program) = parts.next() else { return false };
+
+    let mut command = Command::new(program);
+    command.args(parts).stdin(Stdio::piped());
+    // The program's stdout may be redirected; the pager belongs on the terminal
+    if let Some(tty) = open_tty() {
+        command.stdout(tty);
+    }
+    let Ok(mut child) = command.spawn() else { return false };
+    if let Some(mut stdin) = child.stdin.take() {
+        // Quitting the pager early closes the pipe, which is fine
+        let _ = stdin.write_all(text.as_bytes());
+    }
+    child.wait().is_ok()
+}
+
+/// Arguments that open `path` at `line` in `editor` (a program name or path)
+fn editor_args(editor: &str, path: &str, line: u32) -> Vec<String> {
+    let name = std::path::Path::new(editor)
+        .file_stem()
+        .and_then(|n| n.to_str())
+        .unwrap_or(editor);
+    match name {
+        "vi" | "vim" | "nvim" | "gvim" | "mvim" | "emacs" | "emacsclient" | "nano" | "kak" => {
+            vec![format!("+{}", line), path.to_string()]
+        }
+        "hx" | "helix" | "subl" | "zed" => vec![format!("{}:{}", path, line)],
+        "code" | "code-insiders" | "codium" | "cursor" => vec!["-g".to_string(), format!("{}:{}", path, line)],
+        // Unknown editor: don't guess at its line syntax
+        _ => vec![path.to_string()],
+    }
+}
+
+/// Open the breakpoint's source in `$VISUAL`/`$EDITOR` and wait for it to exit
+fn open_in_editor(con: &mut Console, file: &'static str, line: u32) {
+    use std::process::Command;
+
+    if !matches!(con, Console::Terminal(_)) || !is_tty() {
+        cprintln!(con, "\x1b[90m(the editor can only be opened from the terminal)\x1b[0m");
+        return;
+    }
+    let Some(editor) = ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .find(|e| !e.trim().is_empty())
+    else {
+        cprintln!(con, "\x1b[1;31mSet $VISUAL or $EDITOR to open {}:{}\x1b[0m", file, line);
+        return;
+    };
+    let Some(path) = source_path(file, line) else {
+        cprintln!(con, "\x1b[1;31mCan't find {} on disk\x1b[0m", file);
+        return;
+    };
+
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or_default();
+    let mut command = Command::new(program);
+    command.args(parts).args(editor_args(program, &path.to_string_lossy(), line));
+    // The program's own stdio may be redirected; the editor needs the terminal
+    if let Some(tty) = open_tty() {
+        if let (Ok(input), Ok(output)) = (tty.try_clone(), tty.try_clone()) {
+            command.stdin(input).stdout(output).stderr(tty);
+        }
+    }
+    if let Err(e) = command.status() {
+        cprintln!(con, "\x1b[1;31mFailed to start `{}`: {}\x1b[0m", program, e);
+    }
+}
+
+/// Show the source around the breakpoint: `l [N]` (N lines each side, default 5)
+fn list_source(con: &mut Console, file: &'static str, line: u32, arg: &str) {
+    let context = arg.parse().unwrap_or(5).min(MAX_CONTEXT);
+    match source_snippet(file, line, context, &Colors::get()) {
+        Some(snippet) => {
+            cprintln!(con, "\n\x1b[1;33m─── {}:{} ───\x1b[0m", file, line);
+            for row in snippet {
+                cprintln!(con, "{}", row);
+            }
+            cprintln!(con, "\x1b[1;33m───────────────────\x1b[0m\n");
+        }
+        None => cprintln!(con, "\x1b[90m(source for {} isn't available on disk)\x1b[0m", file),
+    }
+}
+
+/// Show each variable's changes since the last frame at this location
+fn show_diff(con: &mut Console, vars: &[Var], last_frame: Option<&LastFrame>) {
+    let Some(last) = last_frame else {
+        cprintln!(con, "\x1b[90m(first hit shown here, nothing to compare with)\x1b[0m");
+        return;
+    };
+    let c = Colors::get();
+    let title = format!("─── Changes since hit {} ───", last.hit);
+    cprintln!(con, "\n\x1b[1;33m{}\x1b[0m", title);
+    for var in vars {
+        let Some((_, old)) = last.values.iter().find(|(name, _)| *name == var.name) else {
+            cprintln!(con, "\x1b[1;32m{}\x1b[0m \x1b[90m(not shown last time)\x1b[0m", var.name);
+            continue;
+        };
+        let rows = diff_rows(old, &var.source, &c);
+        if rows.is_empty() {
+            cprintln!(con, "\x1b[1;32m{}\x1b[0m \x1b[90m(unchanged)\x1b[0m", var.name);
+            continue;
+        }
+        cprintln!(con, "\x1b[1;32m{}\x1b[0m", var.name);
+        for row in rows {
+            cprintln!(con, "{}", row);
+        }
+    }
+    cprintln!(con, "\x1b[1;33m{}\x1b[0m\n", "─".repeat(title.chars().count()));
+}
+
+/// `text` as a JSON value: itself if it already is JSON, otherwise a JSON string
+fn json_value(text: &str) -> String {
+    #[cfg(feature = "json")]
+    if let Ok(value) = serde_json::from_str::<serde_json::Value>(text) {
+        return serde_json::to_string_pretty(&value).unwrap_or_else(|_| json_string(text));
+    }
+    json_string(text)
+}
+
+/// What `w` writes for `vars`, without colors. Plain text is each full
+/// rendering; `json` gives the value itself for a single variable, or an
+/// object keyed by variable name.
+fn export_text(vars: &[&Var], json: bool) -> String {
+    match (vars, json) {
+        ([var], false) => format!("{}\n", var.source),
+        (vars, false) => vars.iter().map(|v| format!("{} = {}\n\n", v.name, v.source)).collect(),
+        ([var], true) => format!("{}\n", json_value(&var.source)),
+        (vars, true) => {
+            let fields: Vec<String> = vars
+                .iter()
+                .map(|v| format!("  {}: {}", json_string(v.name), json_value(&v.source).replace('\n', "\n  ")))
+                .collect();
+            format!("{{\n{}\n}}\n", fields.join(",\n"))
+        }
+    }
+}
+
+/// What `c` copies: `print_break_serde!` values as JSON, anything else as
+/// the "show more" text without colors
+fn copy_text(vars: &[Var]) -> Option<String> {
+    if !vars.is_empty() && vars.iter().all(|v| v.json) {
+        return Some(export_text(&vars.iter().collect::<Vec<_>>(), true));
+    }
+    let guard = LAST_FULL_OUTPUT.lock().ok()?;
+    guard.as_deref().map(strip_ansi_codes)
+}
+
+/// Write the frame's values to a file: `w <path> [var]`.
+///
+/// A `.json` path gets JSON, anything else plain text.
+fn write_values(con: &mut Console, vars: &[Var], arg: &str) {
+    let (path, name) = match arg.split_once(char::is_whitespace) {
+        Some((path, name)) => (path, Some(name.trim())),
+        None => (arg, None),
+    };
+    if path.is_empty() {
+        cprintln!(con, "\x1b[90musage: w <path> [variable], e.g. w state.json config\x1b[0m");
+        return;
+    }
+    let selected: Vec<&Var> = match name {
+        Some(name) => match vars.iter().find(|v| v.name == name) {
+            Some(var) => vec![var],
+            None => {
+                let names: Vec<&str> = vars.iter().map(|v| v.name).collect();
+                cprintln!(con, "\x1b[1;31mNo variable `{}` here (have: {})\x1b[0m", name, names.join(", "));
+                return;
+            }
+        },
+        None => vars.iter().collect(),
+    };
+    if selected.is_empty() {
+        cprintln!(con, "\x1b[90m(nothing to write)\x1b[0m");
+        return;
+    }
+
+    let json = std::path::Path::new(path).extension().is_some_and(|ext| ext.eq_ignore_ascii_case("json"));
+    let text = export_text(&selected, json);
+    match std::fs::write(path, &text) {
+        Ok(()) => cprintln!(con, "\x1b[1;32mWrote {} bytes to {}\x1b[0m", text.len(), path),
+        Err(e) => cprintln!(con, "\x1b[1;31mFailed to write {}: {}\x1b[0m", path, e),
+    }
+}
+
+/// Show the ignored breakpoint locations, numbered for the `u` command
+fn show_ignored_sites(con: &mut Console) {
+    let sites = ignored_sites();
+    if sites.is_empty() {
+        cprintln!(con, "\x1b[90m(no ignored breakpoints)\x1b[0m");
+        return;
+    }
+    cprintln!(con, "\n\x1b[1;33m─── Ignored Breakpoints ───\x1b[0m");
+    for (i, (file, line)) in sites.iter().enumerate() {
+        cprintln!(con, "\x1b[90m{:>3}.\x1b[0m \x1b[36m{}:{}\x1b[0m", i + 1, file, line);
+    }
+    cprintln!(con, "\x1b[1;33m───────────────────────────\x1b[0m\n");
+}
+
+/// Re-enable ignored locations: `all` or a 1-based index from the `b` listing
+fn unignore_sites(con: &mut Console, arg: &str) {
+    let sites = ignored_sites();
+    if arg == "all" || arg == "*" {
+        for (file, line) in &sites {
+            set_site_ignored(file, *line, false);
+        }
+        cprintln!(con, "\x1b[1;32mRe-enabled {} breakpoint location(s)\x1b[0m", sites.len());
+        return;
+    }
+    match arg.parse::<usize>().ok().and_then(|n| n.checked_sub(1)).and_then(|i| sites.get(i)) {
+        Some((file, line)) => {
+            set_site_ignored(file, *line, false);
+            cprintln!(con, "\x1b[1;32mRe-enabled {}:{}\x1b[0m", file, line);
+        }
+        None => cprintln!(con, "\x1b[1;31mNo ignored breakpoint #{} (see b)\x1b[0m", arg),
+    }
+}
+
+/// Re-render part of a variable: `x <path> [depth]`, e.g. `x user.roles[2]`.
+///
+/// The node at `path` is shown with `depth` levels (default `PRINT_BREAK_DEPTH`)
+/// below it, so a collapsed `{ ... }` can be opened without rerunning.
+fn expand_value(con: &mut Console, vars: &[Var], arg: &str) {
+    let (path, depth) = match arg.rsplit_once(' ').map(|(p, d)| (p.trim(), d.parse::<usize>())) {
+        Some((path, Ok(depth))) => (path, depth),
+        _ => (arg, max_depth()),
+    };
+    if path.is_empty() {
+        cprintln!(con, "\x1b[90musage: x <path> [depth], e.g. x user.roles[2]\x1b[0m");
+        return;
+    }
+
+    // Variable names are expressions (`self.state`), so take the longest one the path starts with
+    let found = vars
+        .iter()
+        .filter_map(|var| {
+            let rest = path.strip_prefix(var.name)?;
+            (rest.is_empty() || rest.starts_with(['.', '['])).then_some((var, rest))
+        })
+        .max_by_key(|(var, _)| var.name.len());
+    let Some((var, rest)) = found else {
+        let names: Vec<&str> = vars.iter().map(|v| v.name).collect();
+        cprintln!(con, "\x1b[1;31mNo variable `{}` here (have: {})\x1b[0m", path, names.join(", "));
+        return;
+    };
+
+    let root = parse_debug(&var.source);
+    match debug_path(&root, rest) {
+        Ok(node) => {
+            let mut out = Vec::new();
+            render_node(node, "", 0, depth, "", "", &Colors::get(), &mut out);
+            cprintln!(con, "\n\x1b[1;33m─── {} ───\x1b[0m", path);
+            for (_, line) in &out {
+                cprintln!(con, "{}", line);
+            }
+            cprintln!(con, "\x1b[1;33m{}\x1b[0m\n", "─".repeat(path.chars().count() + 8));
+        }
+        Err(missing) => cprintln!(con, "\x1b[1;31mNo `{}` in {}\x1b[0m", missing, path),
+    }
+}
+
+/// Lines of context shown around each `/pattern` match
+const SEARCH_CONTEXT: usize = 2;
+
+/// Most matching lines `/pattern` prints before summarizing the rest
+const MAX_SEARCH_MATCHES: usize = 50;
+
+/// Every variable of the frame rendered in full and uncolored, one entry per
+/// line, each with the path of the value on it (`user.roles[2].name`)
+fn frame_lines(vars: &[Var]) -> Vec<(String, String)> {
+    let mut lines = Vec::new();
+    for var in vars {
+        let lead = format!("{} = ", var.name);
+        render_node(&parse_debug(&var.source), var.name, 0, usize::MAX, &lead, "", &Colors::PLAIN, &mut lines);
+    }
+    lines
+}
+
+/// Byte ranges of `pattern` in `line`, ignoring ASCII case unless the
+/// pattern has an uppercase letter
+fn match_ranges(line: &str, pattern: &str) -> Vec<(usize, usize)> {
+    let (line, pattern) = if pattern.chars().any(char::is_uppercase) {
+        (line.to_string(), pattern.to_string())
+    } else {
+        (line.to_ascii_lowercase(), pattern.to_ascii_lowercase())
+    };
+    line.match_indices(&pattern).map(|(start, m)| (start, start + m.len())).collect()
+}
+
+/// Search the whole frame, collapsed values included: `/pattern`.
+///
+/// Matching lines are highlighted and shown with their path and a few lines
+/// of context; the path can be passed straight to `x`.
+fn search_frame(con: &mut Console, vars: &[Var], pattern: &str) {
+    if pattern.is_empty() {
+        cprintln!(con, "\x1b[90musage: /pattern, e.g. /needle (case-insensitive unless it has capitals)\x1b[0m");
+        return;
+    }
+
+    let lines = frame_lines(vars);
+    let hits: Vec<(usize, Vec<(usize, usize)>)> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, (_, text))| (i, match_ranges(text, pattern)))
+        .filter(|(_, ranges)| !ranges.is_empty())
+        .collect();
+    if hits.is_empty() {
+        cprintln!(con, "\x1b[90m(no matches for `{}`)\x1b[0m", pattern);
+        return;
+    }
+
+    let total: usize = hits.iter().map(|(_, ranges)| ranges.len()).sum();
+    let title = format!("─── /{}: {} match{} ───", pattern, total, if total == 1 { "" } else { "es" });
+    cprintln!(con, "\n\x1b[1;33m{}\x1b[0m", title);
+
+    let shown = &hits[..hits.len().min(MAX_SEARCH_MATCHES)];
+    // Matches whose context overlaps share a block
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in shown {
+        let (start, end) = (i.saturating_sub(SEARCH_CONTEXT), (i + SEARCH_CONTEXT + 1).min(lines.len()));
+        match blocks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => blocks.push((start, end)),
+        }
+    }
+    for (n, &(start, end)) in blocks.iter().enumerate() {
+        if n > 0 {
+            cprintln!(con, "\x1b[90m--\x1b[0m");
+        }
+        for (j, (path, text)) in lines.iter().enumerate().take(end).skip(start) {
+            let Some((_, ranges)) = shown.iter().find(|(i, _)| *i == j) else {
+                cprintln!(con, "\x1b[90m  {}\x1b[0m", text);
+                continue;
+            };
+            let mut highlighted = String::new();
+            let mut last = 0;
+            for &(s, e) in ranges {
+                highlighted.push_str(&format!("{}\x1b[1;30;43m{}\x1b[0m", &text[last..s], &text[s..e]));
+                last = e;
+            }
+            highlighted.push_str(&text[last..]);
+            cprintln!(con, "\x1b[36m{}\x1b[0m", path);
+            cprintln!(con, "\x1b[1;33m>\x1b[0m {}", highlighted);
+        }
+    }
+    if hits.len() > shown.len() {
+        cprintln!(con, "\x1b[90m... {} more matching lines\x1b[0m", hits.len() - shown.len());
+    }
+    cprintln!(con, "\x1b[1;33m{}\x1b[0m\n", "─".repeat(title.chars().count()));
+}
+
+/// Seconds to wait at the prompt before auto-continuing (`PRINT_BREAK_TIMEOUT`)
+fn prompt_timeout() -> Option<u64> {
+    std::env::var("PRINT_BREAK_TIMEOUT")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .filter(|&secs| secs > 0)
+}
 
-    // If not a TTY, don't pause - just continue (for CI/piped output)
-    if !is_tty() {
-        eprintln!("(non-interactive mode, continuing...)");
+/// Show `prompt` with a countdown until a key is pressed or `secs` run out.
+///
+/// Returns true if the user started typing (the input is left for the normal
+/// line read), false on timeout. The terminal is put in non-canonical mode
+/// while counting down so the first keypress is seen before Enter.
+#[cfg(unix)]
+fn wait_for_terminal_typing(fd: std::os::unix::io::RawFd, prompt: &str, secs: u64, print: &mut dyn FnMut(&str)) -> bool {
+    // SAFETY: termios is plain data, filled in by tcgetattr before use
+    let mut original: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut original) } != 0 {
+        print(prompt);
         return true;
     }
 
+    let mut cbreak = original;
+    cbreak.c_lflag &= !libc::ICANON;
+    cbreak.c_cc[libc::VMIN] = 1;
+    cbreak.c_cc[libc::VTIME] = 0;
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &cbreak) };
+
+    let mut typed = false;
+    for remaining in (1..=secs).rev() {
+        print(&format!("\r\x1b[K{}\x1b[90m(auto-continue in {}s)\x1b[0m ", prompt, remaining));
+
+        let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        let deadline = Instant::now() + std::time::Duration::from_secs(1);
+        let ready = loop {
+            let wait = deadline.saturating_duration_since(Instant::now()).as_millis() as libc::c_int;
+            match unsafe { libc::poll(&mut pollfd, 1, wait) } {
+                // A signal interrupted the wait - keep waiting out the second
+                -1 if std::io::Error::last_os_error().kind() == std::io::ErrorKind::Interrupted => continue,
+                n => break n,
+            }
+        };
+        if ready > 0 {
+            typed = true;
+            break;
+        }
+        if ready < 0 {
+            // Can't watch the terminal: treat it as a timeout rather than blocking on a read
+            break;
+        }
+    }
+
+    // Back to line mode - keys typed so far stay queued for read_line
+    unsafe { libc::tcsetattr(fd, libc::TCSANOW, &original) };
+    typed
+}
+
+/// The countdown needs termios, so other platforms just wait for input
+#[cfg(not(unix))]
+fn wait_for_terminal_typing(prompt: &str, _secs: u64, print: &mut dyn FnMut(&str)) -> bool {
+    print(prompt);
+    true
+}
+
+/// Handle user input at breakpoint. Returns true if should continue, false if should quit.
+///
+/// `file` and `line` identify the breakpoint location for per-site commands,
+/// `vars` are the frame's variables for commands that re-render them.
+fn handle_input(con: &mut Console, file: &'static str, line: u32, vars: &[Var], last_frame: Option<&LastFrame>) -> bool {
+    // Auto-continue countdown - only until the user starts typing at this breakpoint
+    let mut timeout = prompt_timeout();
+    // The script's default answers at most once per breakpoint, so a default
+    // that doesn't continue (`more`, `l`) can't repeat forever
+    let mut default_used = false;
+    let c = Colors::get();
+
     loop {
-        eprint!("\x1b[90m[Enter, m=more, t=trace, c=copy, s=skip, q=quit, h=help]\x1b[0m ");
-        io::stderr().flush().unwrap();
+        let mut prompt = waiting_note(WAITING.load(Ordering::Relaxed), &c);
+        prompt.push_str(&format!("{}[Enter, m=more, t=trace, c=copy, i=ignore here, s=skip, q=quit, h=help]{} ", c.gray, c.reset));
+
+        let input = if let Some((command, source)) = next_scripted_command(file, line) {
+            if source == SCRIPT_DEFAULT {
+                if default_used {
+                    break;
+                }
+                default_used = true;
+            }
+            // Echo the command so the transcript shows what was answered
+            cprintln!(con, "{}{}{}{} {}({}){}", prompt, c.cyan, command, c.reset, c.gray, source, c.reset);
+            Some(command)
+        } else if !con.is_interactive() {
+            // If not a TTY, don't pause - just continue (for CI/piped output)
+            cprintln!(con, "(non-interactive mode, continuing...)");
+            return true;
+        } else {
+            if let Some(secs) = timeout.take() {
+                if !con.wait_for_typing(&prompt, secs) {
+                    cprintln!(con, "\n\x1b[90m(timed out, continuing...)\x1b[0m");
+                    break;
+                }
+            } else {
+                cprint!(con, "{}", prompt);
+            }
+            con.read_line()
+        };
 
-        let stdin = io::stdin();
-        let mut line = String::new();
-        if stdin.lock().read_line(&mut line).is_ok() {
-            let input = line.trim().to_lowercase();
+        if let Some(buf) = input {
+            // Arguments (paths, patterns) keep their case
+            let raw = buf.trim();
+            let input = raw.to_lowercase();
             match input.as_str() {
                 "q" | "quit" => {
-                    eprintln!("\x1b[1;31mQuitting...\x1b[0m");
+                    cprintln!(con, "\x1b[1;31mQuitting...\x1b[0m");
+                    #[cfg(unix)]
+                    close_remote();
                     std::process::exit(0);
                 }
                 "s" | "skip" => {
-                    eprintln!("\x1b[1;33mSkipping remaining breakpoints...\x1b[0m");
+                    cprintln!(con, "\x1b[1;33mSkipping remaining breakpoints...\x1b[0m");
                     set_skip_all(true);
                     break;
                 }
+                "i" | "ignore" => {
+                    cprintln!(con, "\x1b[1;33mIgnoring breakpoint at {}:{}...\x1b[0m", file, line);
+                    set_site_ignored(file, line, true);
+                    break;
+                }
+                "b" | "breaks" => {
+                    show_ignored_sites(con);
+                    continue;
+                }
+                cmd if cmd.starts_with("u ") => {
+                    unignore_sites(con, cmd[2..].trim());
+                    continue;
+                }
+                cmd if cmd == "x" || cmd.starts_with("x ") => {
+                    expand_value(con, vars, raw[1..].trim());
+                    continue;
+                }
+                cmd if cmd == "w" || cmd.starts_with("w ") => {
+                    write_values(con, vars, raw[1..].trim());
+                    continue;
+                }
+                cmd if cmd.starts_with('/') => {
+                    search_frame(con, vars, raw[1..].trim());
+                    continue;
+                }
                 "m" | "more" => {
-                    // Show full output
-                    if let Ok(guard) = LAST_FULL_OUTPUT.lock() {
-                        if let Some(ref full) = *guard {
-                            eprintln!("\n\x1b[1;33m─── Full Output ───\x1b[0m");
-                            for line in full.lines() {
-                                eprintln!("\x1b[37m{}\x1b[0m", line);
-                            }
-                            eprintln!("\x1b[1;33m───────────────────\x1b[0m\n");
-                        } else {
-                            eprintln!("\x1b[90m(no truncated output to show)\x1b[0m");
+                    show_full_output(con);
+                    continue;
+                }
+                "p" | "page" => {
+                    let full = LAST_FULL_OUTPUT.lock().ok().and_then(|guard| guard.clone());
+                    match full {
+                        Some(full) if page_output(con, &full) => {}
+                        Some(_) => {
+                            cprintln!(con, "\x1b[90m(no pager available, showing inline)\x1b[0m");
+                            show_full_output(con);
                         }
+                        None => cprintln!(con, "\x1b[90m(no output to page)\x1b[0m"),
                     }
                     continue;
                 }
                 "t" | "trace" => {
-                    show_stack_trace();
+                    show_stack_trace(con);
+                    continue;
+                }
+                "d" | "diff" => {
+                    show_diff(con, vars, last_frame);
+                    continue;
+                }
+                cmd if cmd == "l" || cmd.starts_with("l ") => {
+                    list_source(con, file, line, cmd[1..].trim());
+                    continue;
+                }
+                "e" | "edit" => {
+                    open_in_editor(con, file, line);
                     continue;
                 }
                 "c" | "copy" => {
-                    if let Ok(guard) = LAST_FULL_OUTPUT.lock() {
-                        if let Some(ref full) = *guard {
-                            // Strip ANSI codes for clipboard
-                            let clean = strip_ansi_codes(full);
-                            if copy_to_clipboard(&clean) {
-                                eprintln!("\x1b[1;32mCopied to clipboard!\x1b[0m");
-                            } else {
-                                eprintln!("\x1b[1;31mFailed to copy (install xclip or xsel)\x1b[0m");
-                            }
+                    if let Some(clean) = copy_text(vars) {
+                        if copy_to_clipboard(&clean) {
+                            cprintln!(con, "\x1b[1;32mCopied to clipboard!\x1b[0m");
                         } else {
-                            eprintln!("\x1b[90m(nothing to copy)\x1b[0m");
+                            cprintln!(con, "\x1b[1;31mFailed to copy (install xclip or xsel)\x1b[0m");
                         }
+                    } else {
+                        cprintln!(con, "\x1b[90m(nothing to copy)\x1b[0m");
                     }
                     continue;
                 }
                 "h" | "?" | "help" => {
-                    show_help();
+                    show_help(con);
                     continue;
                 }
                 _ => break // Continue
@@ -996,7 +3511,7 @@ pub fn handle_input() -> bool {
             break;
         }
     }
-    eprintln!();
+    cprintln!(con);
     true
 }
 
@@ -1024,9 +3539,10 @@ fn strip_ansi_codes(s: &str) -> String {
 ///
 /// # Features
 ///
-/// - Compiles to nothing in release builds
+/// - Compiles to nothing in release builds, unless the `release` feature is enabled
+/// - Compiles to nothing in any build with the `disabled` feature
 /// - Can be disabled with `PRINT_BREAK=0` environment variable
-/// - Interactive: Enter=continue, q=quit, s=skip all remaining
+/// - Interactive: Enter=continue, q=quit, i=ignore this location, s=skip all remaining
 ///
 /// # Examples
 ///
@@ -1047,72 +3563,37 @@ fn strip_ansi_codes(s: &str) -> String {
 /// print_break!();
 /// ```
 #[macro_export]
-#[cfg(debug_assertions)]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
 macro_rules! print_break {
-    () => {{
-        if $crate::is_enabled() {
-            let break_id = $crate::next_break_id();
-            let elapsed_str = $crate::get_elapsed().map($crate::format_elapsed).unwrap_or_default();
-            $crate::update_break_time();
-
-            let location = format!("{}:{}", file!(), line!());
-            let width = 50;
-            let border = $crate::get_border_style();
-            let c = $crate::Colors::get();
-
-            let h = border.horizontal.to_string();
-
-            eprintln!();
-            eprintln!("{}{}{} BREAK #{} {}{}{}", c.yellow, border.top_left, h, break_id, elapsed_str, h.repeat(width - 14 - break_id.to_string().len() - elapsed_str.len() / 3), c.reset);
-            eprintln!("{}{}{} {}{}{}", c.yellow, border.vertical, c.reset, c.cyan, location, c.reset);
-            eprintln!("{}{}{}{}", c.yellow, border.bottom_left, h.repeat(width), c.reset);
+    ($($var:expr),* $(,)?) => {
+        $crate::__print_break_at!(hit => true; $($var),*)
+    };
+}
 
-            $crate::handle_input();
+/// Shared expansion for the breakpoint macros: records a hit at the call site
+/// and shows the frame when `$when` (which may use the `$hit` count) holds.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __print_break_at {
+    ($hit:ident => $when:expr; $($var:expr),*) => {{
+        if $crate::is_enabled_at(file!(), line!()) {
+            let $hit: usize = $crate::record_hit(file!(), line!(), option_env!("CARGO_MANIFEST_DIR"));
+            if $when {
+                $crate::show_break(file!(), line!(), $hit, &[$($crate::__var!($var)),*]);
+            }
         }
     }};
-    ($($var:expr),+ $(,)?) => {{
-        if $crate::is_enabled() {
-            let break_id = $crate::next_break_id();
-            let elapsed_str = $crate::get_elapsed().map($crate::format_elapsed).unwrap_or_default();
-            $crate::update_break_time();
-
-            let location = format!("{}:{}", file!(), line!());
-            let width = 50;
-            let border = $crate::get_border_style();
-            let c = $crate::Colors::get();
-
-            // Collect full output for "more" option
-            let mut full_output = String::new();
-
-            let h = border.horizontal.to_string();
-
-            eprintln!();
-            eprintln!("{}{}{} BREAK #{} {}{}{}", c.yellow, border.top_left, h, break_id, elapsed_str, h.repeat(width - 14 - break_id.to_string().len() - elapsed_str.len() / 3), c.reset);
-            eprintln!("{}{}{} {}{}{}", c.yellow, border.vertical, c.reset, c.cyan, location, c.reset);
-            eprintln!("{}{}{}{}", c.yellow, border.tee_right, h.repeat(width), c.reset);
-
-            $(
-                let formatted = $crate::format_value(&$var);
-                let name = stringify!($var);
-
-                // Store untruncated version
-                full_output.push_str(&format!("{} = {}\n\n", name, $crate::format_value_full(&$var)));
-
-                if formatted.contains('\n') {
-                    eprintln!("{}{}{} {}{}{}=", c.yellow, border.vertical, c.reset, c.green, name, c.reset);
-                    for line in formatted.lines() {
-                        eprintln!("{}{}{} {}{}{}", c.yellow, border.vertical, c.reset, c.white, line, c.reset);
-                    }
-                } else {
-                    eprintln!("{}{}{} {}{}{} = {}{}{}", c.yellow, border.vertical, c.reset, c.green, name, c.reset, c.white, formatted, c.reset);
-                }
-            )+
-
-            $crate::store_full_output(full_output);
+}
 
-            eprintln!("{}{}{}{}", c.yellow, border.bottom_left, h.repeat(width), c.reset);
-            $crate::handle_input();
-        }
+/// Captures one breakpoint value, rendered with [`BreakFormat`] if the type
+/// implements it, then a registered formatter, and `Debug` otherwise
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __var {
+    ($var:expr) => {{
+        #[allow(unused_imports)]
+        use $crate::{BreakFormatVar as _, DebugVar as _, RefVar as _, RegisteredVar as _};
+        (&&&&$crate::VarRef(&$var)).__print_break_var(stringify!($var))
     }};
 }
 
@@ -1131,7 +3612,7 @@ macro_rules! print_break {
 /// print_break_if!(x > 10, x);  // Breaks because x > 10
 /// ```
 #[macro_export]
-#[cfg(debug_assertions)]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
 macro_rules! print_break_if {
     ($cond:expr) => {{
         if $cond {
@@ -1145,17 +3626,206 @@ macro_rules! print_break_if {
     }};
 }
 
-/// In release builds, print_break_if! compiles to nothing
+/// Hit-count breakpoint - only triggers on the Nth time this location is reached.
+///
+/// The per-location hit count is shown in the `BREAK #` header.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use print_break::print_break_nth;
+///
+/// for i in 0..1000 {
+///     print_break_nth!(500, i);  // Breaks once, on the 500th iteration
+/// }
+/// ```
+#[macro_export]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+macro_rules! print_break_nth {
+    ($n:expr $(, $var:expr)* $(,)?) => {
+        $crate::__print_break_at!(hit => hit == $n; $($var),*)
+    };
+}
+
+/// Hit-count breakpoint - triggers on every Nth time this location is reached.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use print_break::print_break_every;
+///
+/// for i in 0..1000 {
+///     print_break_every!(100, i);  // Breaks on hits 100, 200, 300, ...
+/// }
+/// ```
+#[macro_export]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+macro_rules! print_break_every {
+    ($n:expr $(, $var:expr)* $(,)?) => {
+        $crate::__print_break_at!(hit => hit % usize::max($n, 1) == 0; $($var),*)
+    };
+}
+
+/// Hit-count breakpoint - triggers every time after this location has been reached N times.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use print_break::print_break_after;
+///
+/// for i in 0..1000 {
+///     print_break_after!(990, i);  // Breaks on hits 991 through 1000
+/// }
+/// ```
+#[macro_export]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+macro_rules! print_break_after {
+    ($n:expr $(, $var:expr)* $(,)?) => {
+        $crate::__print_break_at!(hit => hit > $n; $($var),*)
+    };
+}
+
+/// Watch breakpoint - only triggers when the values differ from the previous
+/// time this location was reached.
+///
+/// The first hit records the values without pausing. When a later hit sees
+/// different values, changed variables are shown with the old and new value
+/// side by side; unchanged hits are counted and shown in the header.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use print_break::print_break_changed;
+///
+/// let mut state = "idle";
+/// for i in 0..100 {
+///     if i == 50 {
+///         state = "running";
+///     }
+///     print_break_changed!(state);  // Breaks once, when state becomes "running"
+/// }
+/// ```
+#[macro_export]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+macro_rules! print_break_changed {
+    ($($var:expr),+ $(,)?) => {{
+        if $crate::is_enabled_at(file!(), line!()) {
+            let hit = $crate::record_hit(file!(), line!(), option_env!("CARGO_MANIFEST_DIR"));
+            $crate::show_break_if_changed(file!(), line!(), hit, &[$($crate::__var!($var)),+]);
+        }
+    }};
+}
+
+/// Breakpoint for `serde::Serialize` values, rendered as JSON instead of `Debug`.
+///
+/// Values are serialized to `serde_json::Value`, so the structure shown is
+/// exact (no guessing from `{:#?}` text). Nesting deeper than
+/// `PRINT_BREAK_DEPTH` is collapsed in the frame, while `m` shows and `c`
+/// copies the complete JSON. Needs the `serde` feature.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use print_break::print_break_serde;
+/// use std::collections::BTreeMap;
+///
+/// // Any `Serialize` type works, e.g. your own `#[derive(Serialize)]` structs
+/// let scores = BTreeMap::from([("alice", vec![3, 5]), ("bob", vec![4])]);
+/// print_break_serde!(scores);
+/// ```
+#[cfg(feature = "serde")]
+#[macro_export]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+macro_rules! print_break_serde {
+    ($($var:expr),* $(,)?) => {{
+        if $crate::is_enabled_at(file!(), line!()) {
+            let hit = $crate::record_hit(file!(), line!(), option_env!("CARGO_MANIFEST_DIR"));
+            $crate::show_break(file!(), line!(), hit, &[$($crate::Var::serde(stringify!($var), &$var)),*]);
+        }
+    }};
+}
+
+/// Prints variables like [`print_break!`] but never pauses - a colorized `dbg!`.
+///
+/// Honors `PRINT_BREAK=0`, ignored locations and the release-build no-op.
+/// Setting `PRINT_BREAK=trace` makes every `print_break!` behave like this.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use print_break::print_trace;
+///
+/// for i in 0..3 {
+///     print_trace!(i);  // Prints a frame for each iteration without stopping
+/// }
+/// ```
+#[macro_export]
+#[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+macro_rules! print_trace {
+    ($($var:expr),* $(,)?) => {{
+        if $crate::is_enabled_at(file!(), line!()) {
+            let hit = $crate::record_hit(file!(), line!(), option_env!("CARGO_MANIFEST_DIR"));
+            $crate::show_trace(file!(), line!(), hit, &[$($crate::__var!($var)),*]);
+        }
+    }};
+}
+
+/// In release builds (or with the `disabled` feature), print_break_serde! compiles to nothing
+#[cfg(feature = "serde")]
 #[macro_export]
-#[cfg(not(debug_assertions))]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
+macro_rules! print_break_serde {
+    () => {{}};
+    ($($var:expr),+ $(,)?) => {{}};
+}
+
+/// In release builds (or with the `disabled` feature), print_trace! compiles to nothing
+#[macro_export]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
+macro_rules! print_trace {
+    () => {{}};
+    ($($var:expr),+ $(,)?) => {{}};
+}
+
+/// In release builds (or with the `disabled` feature), print_break_changed! compiles to nothing
+#[macro_export]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
+macro_rules! print_break_changed {
+    ($($var:expr),+ $(,)?) => {{}};
+}
+
+/// In release builds (or with the `disabled` feature), print_break_nth! compiles to nothing
+#[macro_export]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
+macro_rules! print_break_nth {
+    ($n:expr $(, $var:expr)* $(,)?) => {{}};
+}
+
+/// In release builds (or with the `disabled` feature), print_break_every! compiles to nothing
+#[macro_export]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
+macro_rules! print_break_every {
+    ($n:expr $(, $var:expr)* $(,)?) => {{}};
+}
+
+/// In release builds (or with the `disabled` feature), print_break_after! compiles to nothing
+#[macro_export]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
+macro_rules! print_break_after {
+    ($n:expr $(, $var:expr)* $(,)?) => {{}};
+}
+
+/// In release builds (or with the `disabled` feature), print_break_if! compiles to nothing
+#[macro_export]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
 macro_rules! print_break_if {
     ($cond:expr) => {{}};
     ($cond:expr, $($var:expr),+ $(,)?) => {{}};
 }
 
-/// In release builds, print_break! compiles to nothing
+/// In release builds (or with the `disabled` feature), print_break! compiles to nothing
 #[macro_export]
-#[cfg(not(debug_assertions))]
+#[cfg(not(all(not(feature = "disabled"), any(debug_assertions, feature = "release"))))]
 macro_rules! print_break {
     () => {{}};
     ($($var:expr),+ $(,)?) => {{}};
@@ -1165,7 +3835,23 @@ macro_rules! print_break {
 mod tests {
     use super::*;
 
+    /// Serializes tests that read or modify `PRINT_BREAK*` environment variables
+    static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+    /// A variable whose frame rendering hides its contents, as depth collapsing
+    /// does in a terminal
+    fn collapsed_var(source: &str) -> Var {
+        Var {
+            name: "state",
+            formatted: "State { ... }".into(),
+            full: "State { ... }".into(),
+            source: source.into(),
+            json: false,
+        }
+    }
+
     #[test]
+    #[cfg(feature = "json")]
     fn format_json_string() {
         let json = r#"{"name": "test", "value": 42}"#;
         let formatted = format_value(&json);
@@ -1182,6 +3868,7 @@ mod tests {
 
     #[test]
     fn format_struct() {
+        #[allow(dead_code)]
         #[derive(Debug)]
         struct Test { a: i32, b: String }
 
@@ -1190,6 +3877,15 @@ mod tests {
         assert!(formatted.contains("Test"));
     }
 
+    #[test]
+    #[cfg(not(feature = "json"))]
+    fn json_string_without_feature_is_plain() {
+        let json = r#"{"name": "test"}"#;
+        let formatted = format_value(&json);
+        assert!(formatted.starts_with("(string, "));
+        assert!(formatted.contains(json));
+    }
+
     #[test]
     fn truncation_works() {
         let long_vec: Vec<i32> = (0..1000).collect();
@@ -1199,6 +3895,7 @@ mod tests {
 
     #[test]
     fn env_var_disable() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
         std::env::set_var("PRINT_BREAK", "0");
         assert!(!is_enabled());
         std::env::set_var("PRINT_BREAK", "1");
@@ -1207,6 +3904,363 @@ mod tests {
     }
 
     #[test]
+    fn env_var_trace() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        std::env::set_var("PRINT_BREAK", "trace");
+        assert!(is_enabled());
+        assert!(is_trace_mode());
+        std::env::remove_var("PRINT_BREAK");
+        assert!(!is_trace_mode());
+    }
+
+    #[test]
+    fn ignore_single_site() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let file = "src/ignore_test.rs";
+        assert!(is_enabled_at(file, 10));
+        set_site_ignored(file, 10, true);
+        assert!(!is_enabled_at(file, 10));
+        assert!(is_enabled_at(file, 11));
+        assert!(ignored_sites().contains(&(file, 10)));
+        set_site_ignored(file, 10, false);
+        assert!(is_enabled_at(file, 10));
+    }
+
+    #[test]
+    fn hits_counted_per_site() {
+        let file = "src/hits_test.rs";
+        assert_eq!(record_hit(file, 1, None), 1);
+        assert_eq!(record_hit(file, 1, None), 2);
+        assert_eq!(record_hit(file, 2, None), 1);
+        assert_eq!(record_hit(file, 1, None), 3);
+    }
+
+    #[test]
+    fn editor_args_use_each_line_syntax() {
+        assert_eq!(editor_args("/usr/bin/nvim", "src/main.rs", 8), ["+8", "src/main.rs"]);
+        assert_eq!(editor_args("hx", "src/main.rs", 8), ["src/main.rs:8"]);
+        assert_eq!(editor_args("code", "src/main.rs", 8), ["-g", "src/main.rs:8"]);
+        assert_eq!(editor_args("ed", "src/main.rs", 8), ["src/main.rs"]);
+    }
+
+    #[test]
+    fn snippet_marks_breakpoint_line() {
+        let source = "fn main() {\n    let x = 1;\n    print_break!(x);\n}\n";
+        let rows = render_snippet(source, 3, 1, &Colors::PLAIN);
+        assert_eq!(rows, ["  2 │     let x = 1;", "> 3 │     print_break!(x);", "  4 │ }"]);
+        assert!(render_snippet(source, 40, 1, &Colors::PLAIN).is_empty());
+        assert_eq!(render_snippet(source, 3, usize::MAX, &Colors::PLAIN).len(), 4);
+    }
+
+    #[test]
+    fn diff_keeps_context_around_changes() {
+        let old = "[\n1,\n2,\n3,\n4,\n5,\n6,\n7,\n]";
+        let new = "[\n1,\n2,\n3,\n4,\n5,\n60,\n7,\n8,\n]";
+        let rows = diff_rows(old, new, &Colors::PLAIN);
+        assert_eq!(rows, ["  ⋮", "  4,", "  5,", "- 6,", "+ 60,", "  7,", "+ 8,", "  ]"]);
+        assert!(diff_rows(old, old, &Colors::PLAIN).is_empty());
+    }
+
+    #[test]
+    fn last_frame_keeps_uncollapsed_values() {
+        swap_last_frame("src/last_frame_test.rs", 1, 1, &[collapsed_var("State { a: 1 }")]);
+        let last = swap_last_frame("src/last_frame_test.rs", 1, 2, &[]).unwrap();
+        assert_eq!(last.hit, 1);
+        assert_eq!(last.values, [("state", "State { a: 1 }".to_string())]);
+    }
+
+    #[test]
+    fn export_picks_format() {
+        let config = Var::custom("config", "{\"port\": 8080}".to_string());
+        let name = Var::custom("name", "ferris".to_string());
+        assert_eq!(export_text(&[&name], false), "ferris\n");
+        assert_eq!(export_text(&[&config, &name], false), "config = {\"port\": 8080}\n\nname = ferris\n\n");
+        assert_eq!(export_text(&[&collapsed_var("State { a: 1 }")], false), "State { a: 1 }\n");
+        #[cfg(feature = "json")]
+        {
+            assert_eq!(export_text(&[&config], true), "{\n  \"port\": 8080\n}\n");
+            assert_eq!(export_text(&[&config, &name], true), "{\n  \"config\": {\n    \"port\": 8080\n  },\n  \"name\": \"ferris\"\n}\n");
+        }
+    }
+
+    #[test]
+    fn side_by_side_pads_old_column() {
+        let rows = side_by_side("1", "2\n3", &Colors::PLAIN);
+        assert_eq!(rows.len(), 3);
+        assert!(rows[1].starts_with("1 "));
+        assert!(rows[1].ends_with("│ 2"));
+        assert!(rows[2].ends_with("│ 3"));
+    }
+
+    #[test]
+    fn thread_label_uses_name() {
+        let label = std::thread::Builder::new()
+            .name("worker-1".to_string())
+            .spawn(thread_label)
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(label, "worker-1");
+    }
+
+    #[test]
+    fn memory_sink_receives_frames() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let sink = MemorySink::new();
+        set_sink(sink.clone());
+        // Diagnostics go to the sink too
+        std::env::set_var("PRINT_BREAK_LOG", "/nonexistent/print-break/log.jsonl");
+        show_trace("src/sink.rs", 3, 1, &[Var::new("answer", &42)]);
+        std::env::remove_var("PRINT_BREAK_LOG");
+        reset_sink();
+
+        let output = sink.take();
+        assert!(output.contains("print-break: can't write PRINT_BREAK_LOG"));
+        assert!(output.contains("TRACE #"));
+        assert!(output.contains("src/sink.rs:3"));
+        assert!(output.contains("answer = 42"));
+        assert!(sink.contents().is_empty());
+    }
+
+    #[test]
+    fn threads_take_turns_at_breakpoints() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let sink = MemorySink::new();
+        set_sink(sink.clone());
+        let guard = lock_breakpoint();
+        let workers: Vec<_> = (0..2)
+            .map(|n| {
+                std::thread::Builder::new()
+                    .name(format!("worker-{}", n))
+                    .spawn(move || show_trace("src/threads.rs", 1, 1, &[Var::new("worker", &n)]))
+                    .unwrap()
+            })
+            .collect();
+        // Both queue behind the frame this thread holds
+        while WAITING.load(Ordering::Relaxed) < 2 {
+            std::thread::yield_now();
+        }
+        assert_eq!(waiting_note(WAITING.load(Ordering::Relaxed), &Colors::PLAIN), "(2 threads waiting) ");
+        drop(guard);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+        reset_sink();
+
+        // Every value line belongs to the last header seen, so frames didn't interleave
+        let output = sink.take();
+        let mut current = None;
+        let (mut frames, mut values) = (0, 0);
+        for line in output.lines() {
+            if let Some(n) = (0..2).find(|n| line.ends_with(&format!("[worker-{}]", n))) {
+                current = Some(n);
+                frames += 1;
+            } else if let Some(value) = line.strip_prefix("│ worker = ") {
+                assert_eq!(Some(value.to_string()), current.map(|n: i32| n.to_string()));
+                values += 1;
+            }
+        }
+        assert_eq!((frames, values), (2, 2));
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
+    fn log_records_round_trip() {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let path = std::env::temp_dir().join(format!("print-break-log-{}.jsonl", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        std::env::set_var("PRINT_BREAK_LOG", &path);
+        let vars = [Var::custom("reply", "say \"hi\"\nbye".to_string())];
+        let elapsed = Some(std::time::Duration::from_millis(1500));
+        log_break(&LogRecord { break_id: 7, file: "src/log.rs", line: 12, hit: 3, elapsed, thread: "main".to_string(), vars: &vars });
+        std::env::remove_var("PRINT_BREAK_LOG");
+
+        let text = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(text.lines().count(), 1);
+        let record: serde_json::Value = serde_json::from_str(text.trim_end()).unwrap();
+        assert_eq!(
+            record,
+            serde_json::json!({
+                "id": 7, "file": "src/log.rs", "line": 12, "hit": 3, "elapsed": 1.5, "thread": "main",
+                "vars": [{"name": "reply", "value": "say \"hi\"\nbye"}],
+            })
+        );
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+    fn capture_records_events() {
+        // `paused` depends on `PRINT_BREAK=trace`
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        let events = capture(|| {
+            for i in 0..4 {
+                crate::print_break_every!(2, i);
+            }
+            let name = "ferris";
+            crate::print_trace!(name);
+        });
+        assert_eq!(events.len(), 3);
+        assert_eq!((events[0].hit, events[0].var("i")), (2, Some("1")));
+        assert_eq!((events[1].hit, events[1].var("i")), (4, Some("3")));
+        assert!(events[0].paused);
+        assert!(!events[2].paused);
+        assert_eq!(events[2].var("name"), Some("ferris"));
+        assert!(!is_capturing());
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+    fn capture_ignores_collapse_depth() {
+        let nested = (((((((("deep",),),),),),),),);
+        let events = capture(|| crate::print_trace!(nested));
+        assert_eq!(events[0].var("nested"), Some(format!("{:#?}", nested).as_str()));
+    }
+
+    #[test]
+    fn changes_below_collapse_depth_still_pause() {
+        let events = capture(|| {
+            for (hit, inner) in ["State { a: A { b: 1 } }", "State { a: A { b: 2 } }"].iter().enumerate() {
+                show_break_if_changed("src/changed_test.rs", 1, hit + 1, &[collapsed_var(inner)]);
+            }
+        });
+        assert_eq!(events.len(), 1);
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+    fn custom_formatters_replace_debug() {
+        struct Cents(i64);
+
+        impl BreakFormat for Cents {
+            fn break_format(&self) -> String {
+                format!("${}.{:02}", self.0 / 100, self.0 % 100)
+            }
+        }
+
+        #[derive(Debug)]
+        struct Meters(f64);
+
+        register_formatter::<Meters>(|m: &Meters| format!("{} m", m.0));
+        let price = Cents(1250);
+        let price_ref = &price;
+        let distance = Meters(2.5);
+        let events = capture(|| crate::print_break!(price, price_ref, distance, Some(Meters(1.0))));
+        unregister_formatter::<Meters>();
+
+        assert_eq!(events[0].var("price"), Some("$12.50"));
+        assert_eq!(events[0].var("price_ref"), Some("$12.50"));
+        assert_eq!(events[0].var("distance"), Some("2.5 m"));
+        assert!(events[0].var("Some(Meters(1.0))").unwrap().contains("Meters("));
+    }
+
+    #[test]
+    #[cfg(all(not(feature = "disabled"), any(debug_assertions, feature = "release")))]
+    fn borrowed_values_skip_registered_formatters() {
+        #[derive(Debug)]
+        struct Label<'a>(&'a str);
+
+        register_formatter::<Label<'static>>(|label: &Label<'static>| label.0.to_uppercase());
+        let text = String::from("borrowed");
+        let label = Label(&text);
+        let label_ref = &label;
+        let events = capture(|| crate::print_break!(label_ref));
+        unregister_formatter::<Label<'static>>();
+
+        assert!(events[0].var("label_ref").unwrap().contains("\"borrowed\""));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn serde_values_collapse_in_frame_only() {
+        let value = serde_json::json!({"id": 7, "tags": ["a"], "owner": {"name": "ferris"}});
+        let mut collapsed = String::new();
+        json_collapsed(&value, 0, 1, &mut collapsed);
+        assert_eq!(collapsed, "{\n  \"id\": 7,\n  \"owner\": { … },\n  \"tags\": [ … ]\n}");
+
+        let var = Var::serde("value", &value);
+        let full: serde_json::Value = serde_json::from_str(&strip_ansi_codes(&var.full)).unwrap();
+        assert_eq!(full, value);
+        let copied: serde_json::Value = serde_json::from_str(&copy_text(&[var]).unwrap()).unwrap();
+        assert_eq!(copied, value);
+    }
+
+    #[test]
+    fn debug_parser_handles_tricky_strings() {
+        let node = parse_debug(r#"User { name: "a {b}: c\n\"q\"", close: '}', tags: {"k: 1": [1, -2.5e-3]} }"#);
+        let DebugNode::Struct { name, fields, .. } = node else { panic!("not a struct: {:?}", node) };
+        assert_eq!(name, "User");
+        assert_eq!(fields[0], ("name".to_string(), DebugNode::Str(r#""a {b}: c\n\"q\"""#.to_string())));
+        assert_eq!(fields[1].1, DebugNode::Str("'}'".to_string()));
+        let DebugNode::Map(entries) = &fields[2].1 else { panic!("not a map: {:?}", fields[2].1) };
+        assert_eq!(entries[0].0, DebugNode::Str(r#""k: 1""#.to_string()));
+        assert_eq!(entries[0].1, DebugNode::List(vec![DebugNode::Number("1".into()), DebugNode::Number("-2.5e-3".into())]));
+    }
+
+    #[test]
+    fn debug_parser_keeps_custom_output() {
+        let node = parse_debug("Wallet {\n  cash: 12.50 EUR,\n      range: 1..5,\n  ..\n}");
+        assert_eq!(node, DebugNode::Struct {
+            name: "Wallet".into(),
+            fields: vec![
+                ("cash".into(), DebugNode::Atom("12.50 EUR".into())),
+                ("range".into(), DebugNode::Number("1..5".into())),
+            ],
+            non_exhaustive: true,
+        });
+        assert_eq!(parse_debug("oops { not: debug"), DebugNode::Atom("oops { not: debug".into()));
+    }
+
+    #[test]
+    fn debug_render_collapses_by_depth() {
+        #[derive(Debug)]
+        #[allow(dead_code)]
+        struct Outer { label: &'static str, inner: Option<(u8, u8)>, empty: Vec<u8> }
+
+        let value = Outer { label: "{ not: nested }", inner: Some((1, 2)), empty: vec![] };
+        let rendered = render_debug(&format!("{:#?}", value), 2, &Colors::PLAIN);
+        assert_eq!(rendered, "Outer {\n│ label: \"{ not: nested }\",\n│ inner: Some(\n│ │ ( ... ),\n│ ),\n│ empty: [],\n}");
+    }
+
+    #[test]
+    fn debug_paths_find_nested_nodes() {
+        let root = parse_debug(r#"User { roles: [Role { name: "a" }, Role { name: "b]" }], meta: {"k.1": Some((1, 2))} }"#);
+        assert_eq!(debug_path(&root, ".roles[1].name"), Ok(&DebugNode::Str(r#""b]""#.into())));
+        assert_eq!(debug_path(&root, r#".meta["k.1"].1"#), Ok(&DebugNode::Number("2".into())));
+        assert_eq!(debug_path(&root, ".roles[2].name"), Err("[2]".to_string()));
+        assert_eq!(debug_path(&root, ".missing"), Err(".missing".to_string()));
+    }
+
+    #[test]
+    fn search_lines_carry_value_paths() {
+        let vars = [Var::custom("user", r#"User { roles: [Role { name: "a" }, Role { name: "Admin" }] }"#.to_string())];
+        let lines = frame_lines(&vars);
+        let (path, text) = lines.iter().find(|(_, text)| !match_ranges(text, "admin").is_empty()).unwrap();
+        assert_eq!(path, "user.roles[1].name");
+        assert_eq!(match_ranges(text, "Admin").len(), 1);
+        assert!(match_ranges(text, "ADMIN").is_empty());
+        assert_eq!(lines[0], ("user".to_string(), "user = User {".to_string()));
+    }
+
+    #[test]
+    fn script_site_commands_come_first() {
+        let mut script = Script::parse("# setup\nmore\n\nmain.rs:7 skip\ncontinue\nsrc/main.rs:7\n");
+        assert_eq!(script.next("src/main.rs", 7).as_deref(), Some("skip"));
+        assert_eq!(script.next("src/lib.rs", 7).as_deref(), Some("more"));
+        assert_eq!(script.next("src/main.rs", 7).as_deref(), Some("continue"));
+        assert_eq!(script.next("src/main.rs", 7).as_deref(), Some("continue"));
+        assert_eq!(script.next("src/main.rs", 7), None);
+    }
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(json_string("a \"b\"\n\\c"), r#""a \"b\"\n\\c""#);
+        assert_eq!(json_string("\x1b"), r#""\u001b""#);
+    }
+
+    #[test]
+    #[cfg(feature = "json")]
     fn json_array_elements_same_color() {
         // Regression test: array elements should all be values (magenta), not keys (cyan)
         // Test colorize_json directly since format_value skips colors in non-TTY test env